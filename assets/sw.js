@@ -4,6 +4,7 @@ var filesToCache = [
   './index.html',
   './terratree.js',
   './terratree_bg.wasm',
+  './terraria.pack',
 ];
 
 /* Start the service worker and cache all of the app's content */
@@ -0,0 +1,181 @@
+//! Saving and opening files the user picks: through a file dialog on native, and as a download
+//! or upload on web.
+
+/// Kinds of files to offer in a dialog, as a name and the extensions that belong to it.
+pub type Filter<'a> = (&'a str, &'a [&'a str]);
+
+/// A file the user picked to open.
+pub struct OpenedFile {
+    pub name: String,
+    pub contents: String,
+}
+
+/// Asks where to save a file, suggesting `file_name`, and writes what `contents` returns for the
+/// name that was picked. The dialog runs in the background, so the app keeps drawing meanwhile;
+/// `done` is called with the result once the file is written, and not at all if the user cancels.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(
+    file_name: &str,
+    filters: &[Filter<'_>],
+    contents: impl FnOnce(&str) -> anyhow::Result<Vec<u8>> + Send + 'static,
+    done: impl FnOnce(anyhow::Result<()>) + Send + 'static,
+) {
+    let dialog = dialog(filters).set_file_name(file_name);
+
+    std::thread::spawn(move || {
+        let Some(file) = pollster::block_on(dialog.save_file()) else {
+            return;
+        };
+
+        done(
+            contents(&file.file_name())
+                .and_then(|contents| Ok(pollster::block_on(file.write(&contents))?)),
+        );
+    });
+}
+
+/// Downloads a file named `file_name` with what `contents` returns for it, and calls `done` once
+/// the download is started.
+#[cfg(target_arch = "wasm32")]
+pub fn save(
+    file_name: &str,
+    _filters: &[Filter<'_>],
+    contents: impl FnOnce(&str) -> anyhow::Result<Vec<u8>> + Send + 'static,
+    done: impl FnOnce(anyhow::Result<()>) + Send + 'static,
+) {
+    done(download(file_name, contents(file_name)));
+}
+
+/// How long a download's object URL is kept. Revoking it right after starting the download can
+/// cancel it in some browsers.
+#[cfg(target_arch = "wasm32")]
+const DOWNLOAD_URL_LIFETIME_MS: i32 = 60_000;
+
+#[cfg(target_arch = "wasm32")]
+fn download(file_name: &str, contents: anyhow::Result<Vec<u8>>) -> anyhow::Result<()> {
+    use eframe::wasm_bindgen::{JsCast as _, closure::Closure};
+    use web_sys::{
+        Blob, HtmlAnchorElement, Url,
+        js_sys::{Array, Uint8Array},
+    };
+
+    let contents = contents?;
+
+    let bytes = Uint8Array::from(contents.as_slice());
+    let blob = Blob::new_with_u8_array_sequence(&Array::of1(&bytes)).map_err(js_error)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+    let anchor = document()?
+        .create_element("a")
+        .map_err(js_error)?
+        .unchecked_into::<HtmlAnchorElement>();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    let revoke = Closure::once_into_js(move || {
+        Url::revoke_object_url(&url).ok();
+    });
+    web_sys::window()
+        .ok_or_else(|| anyhow::anyhow!("no window"))?
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            DOWNLOAD_URL_LIFETIME_MS,
+        )
+        .map_err(js_error)?;
+
+    Ok(())
+}
+
+/// Asks for a file to open and calls `done` with it once it's read. The dialog runs in the
+/// background, like for [`save`]. `done` isn't called if the user cancels.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open(
+    filters: &[Filter<'_>],
+    done: impl FnOnce(anyhow::Result<OpenedFile>) + Send + 'static,
+) {
+    let dialog = dialog(filters);
+
+    std::thread::spawn(move || {
+        let Some(file) = pollster::block_on(dialog.pick_file()) else {
+            return;
+        };
+
+        let name = file.file_name();
+        done(
+            String::from_utf8(pollster::block_on(file.read()))
+                .map_err(|_| anyhow::anyhow!("{name} is not a text file"))
+                .map(|contents| OpenedFile { name, contents }),
+        );
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn dialog(filters: &[Filter<'_>]) -> rfd::AsyncFileDialog {
+    filters
+        .iter()
+        .fold(rfd::AsyncFileDialog::new(), |dialog, (name, extensions)| {
+            dialog.add_filter(*name, extensions)
+        })
+}
+
+/// Asks for a file to upload and calls `done` with it once it's read. `done` isn't called if
+/// the user cancels.
+#[cfg(target_arch = "wasm32")]
+pub fn open(
+    filters: &[Filter<'_>],
+    done: impl FnOnce(anyhow::Result<OpenedFile>) + Send + 'static,
+) {
+    use eframe::wasm_bindgen::{JsCast as _, closure::Closure};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::HtmlInputElement;
+
+    let input = match document().and_then(|d| d.create_element("input").map_err(js_error)) {
+        Ok(input) => input.unchecked_into::<HtmlInputElement>(),
+        Err(e) => return done(Err(e)),
+    };
+
+    let accept = filters
+        .iter()
+        .flat_map(|(_, extensions)| extensions.iter())
+        .map(|extension| format!(".{extension}"))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    input.set_type("file");
+    input.set_accept(&accept);
+
+    let picked = input.clone();
+    let on_change = Closure::once_into_js(move || {
+        let Some(file) = picked.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let contents = JsFuture::from(file.text())
+                .await
+                .map_err(js_error)
+                .and_then(|text| {
+                    text.as_string()
+                        .ok_or_else(|| anyhow::anyhow!("{} is not a text file", file.name()))
+                });
+
+            done(contents.map(|contents| OpenedFile { name: file.name(), contents }));
+        });
+    });
+
+    input.set_onchange(Some(on_change.unchecked_ref()));
+    input.click();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn document() -> anyhow::Result<web_sys::Document> {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| anyhow::anyhow!("no document"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(error: eframe::wasm_bindgen::JsValue) -> anyhow::Error {
+    anyhow::anyhow!("{error:?}")
+}
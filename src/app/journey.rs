@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wiki_data::ArchivedItem;
+
+use crate::pack;
+
+/// Research progress of a Journey mode character. Items are kept by qualified name, so progress
+/// survives the data packs changing.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Journey {
+    researched: HashMap<String, u32>,
+}
+
+impl Journey {
+    pub fn researched(&self, item: &'static ArchivedItem) -> u32 {
+        self.researched
+            .get(pack::get().qualified_name(item).as_ref())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// How many more of the item need to be researched, or `None` if it can't be researched.
+    pub fn remaining(&self, item: &'static ArchivedItem) -> Option<u32> {
+        let needed = item.research.as_ref()?.to_native();
+        Some(needed.saturating_sub(self.researched(item)))
+    }
+
+    pub fn set_researched(&mut self, item: &'static ArchivedItem, count: u32) {
+        let name = pack::get().qualified_name(item);
+
+        if count == 0 {
+            self.researched.remove(name.as_ref());
+        } else {
+            self.researched.insert(name.into_owned(), count);
+        }
+    }
+
+    /// Research progress over all items that can be researched, as `(researched, total)`. Only
+    /// the researched items are looked up by name, the rest are just counted.
+    pub fn progress(&self) -> (usize, usize) {
+        let packs = pack::get();
+
+        let total = packs
+            .items()
+            .filter(|(_, item)| item.research.is_some())
+            .count();
+
+        let done = self
+            .researched
+            .iter()
+            .filter(|&(name, &count)| {
+                packs
+                    .item_by_name(name)
+                    .and_then(|item| item.research.as_ref())
+                    .is_some_and(|needed| count >= needed.to_native())
+            })
+            .count();
+
+        (done, total)
+    }
+
+    /// Context menu entries to edit the research progress of an item.
+    pub fn context_menu(&mut self, ui: &mut egui::Ui, item: &'static ArchivedItem) {
+        let Some(needed) = item.research.as_ref().map(|r| r.to_native()) else {
+            ui.add_enabled(false, egui::Button::new("Research"))
+                .on_disabled_hover_text("Can't be researched");
+            return;
+        };
+
+        let mut researched = self.researched(item);
+
+        ui.horizontal(|ui| {
+            ui.label("Researched");
+            ui.add(
+                egui::DragValue::new(&mut researched)
+                    .range(0..=needed)
+                    .suffix(format!(" / {needed}")),
+            );
+        });
+
+        if ui.button("Research fully").clicked() {
+            researched = needed;
+        }
+
+        if researched != self.researched(item) {
+            self.set_researched(item, researched);
+        }
+    }
+}
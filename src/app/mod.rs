@@ -3,38 +3,85 @@ use std::sync::Arc;
 use egui::{Align2, UiBuilder, Vec2, vec2};
 use serde::{Deserialize, Serialize};
 
-use self::pages::tree::Node;
+use self::{
+    journey::Journey,
+    pages::tree::{CachedShoppingList, SharedTree, TreeFiles, Trees},
+};
+use crate::{pack, tdata};
+
+mod files;
+mod journey;
 
 mod pages {
     pub mod tree;
 }
 
+mod widgets {
+    pub mod coins;
+    pub mod rarity;
+    pub mod tooltip;
+}
+
 mod storage_keys {
     pub const UI_STATE: &str = "ui_state";
+    /// Where the single tree was saved before there could be several.
     pub const TREE_ROOT: &str = "tree_root";
+    pub const TREES: &str = "trees";
+    pub const JOURNEY: &str = "journey";
 }
 
 pub struct TerratreeApp {
     ui_state: UiState,
-    tree_root: Node,
+    pack: PackState,
+    /// Loaded from storage once the data packs are ready, since nodes refer to its items.
+    trees: Option<Trees>,
+    /// The share link the app was opened with, until the data packs are loaded to read it.
+    share_link: Option<String>,
+    shared: Option<SharedTree>,
+    shopping_list: CachedShoppingList,
+    journey: Journey,
+    tree_files: TreeFiles,
     dragging: Dragging,
 }
 
+enum PackState {
+    Loading(pack::Loader),
+    Failed(String),
+    Ready,
+}
+
 #[derive(Default, Deserialize, Serialize)]
 #[serde(default)]
 struct UiState {
     item_search: String,
+    show_shopping_list: bool,
+    journey_mode: bool,
+    only_unresearched: bool,
+    tag_filter: Option<String>,
+    milestone_filter: Option<String>,
 }
 
 #[derive(Default)]
-enum Dragging {
+pub(crate) enum Dragging {
     #[default]
     None,
-    Item(&'static wiki_data::Item),
+    Item(&'static wiki_data::ArchivedItem),
 }
 
 impl TerratreeApp {
+    /// Opens the app with the packs from the `pack` query parameters of the page.
+    #[cfg(target_arch = "wasm32")]
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self::with_sources(cc, pack::Source::from_query(cc))
+    }
+
+    /// Opens the app with the packs given on the command line, see [`crate::cli::Args`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_packs(cc: &eframe::CreationContext<'_>, packs: Vec<String>) -> Self {
+        Self::with_sources(cc, pack::Source::from_args(packs))
+    }
+
+    fn with_sources(cc: &eframe::CreationContext<'_>, sources: Vec<pack::Source>) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
@@ -51,47 +98,127 @@ impl TerratreeApp {
             .and_then(|storage| eframe::get_value(storage, storage_keys::UI_STATE))
             .unwrap_or_default();
 
-        let tree_root = cc
+        let journey = cc
             .storage
-            .and_then(|storage| eframe::get_value(storage, storage_keys::TREE_ROOT))
-            .unwrap_or(Node::from_name("Copper Pickaxe").unwrap());
+            .and_then(|storage| eframe::get_value(storage, storage_keys::JOURNEY))
+            .unwrap_or_default();
 
         Self {
             ui_state,
-            tree_root,
+            pack: PackState::Loading(pack::Loader::start(sources, &cc.egui_ctx)),
+            trees: None,
+            share_link: SharedTree::link(cc),
+            shared: None,
+            shopping_list: CachedShoppingList::default(),
+            journey,
+            tree_files: TreeFiles::default(),
             dragging: Dragging::default(),
         }
     }
+
+    /// Waits for the data packs, drawing a loading screen in the meantime. Returns whether the
+    /// packs are ready.
+    fn wait_for_packs(&mut self, ctx: &egui::Context, frame: &eframe::Frame) -> bool {
+        if let PackState::Loading(loader) = &mut self.pack {
+            match loader.poll() {
+                None => {}
+                Some(Ok(())) => {
+                    for problem in tdata::validate(|name| pack::get().item_by_name(name).is_some())
+                    {
+                        log::warn!("{problem}");
+                    }
+
+                    let trees = Trees::load(frame.storage());
+                    self.tree_files.left_out_of_storage(trees.missing());
+                    self.trees = Some(trees);
+                    self.shared = self
+                        .share_link
+                        .take()
+                        .and_then(|link| SharedTree::open(&link));
+                    self.pack = PackState::Ready;
+                }
+                Some(Err(e)) => {
+                    log::error!("failed to load data pack: {e}");
+                    self.pack = PackState::Failed(e);
+                }
+            }
+        }
+
+        match &self.pack {
+            PackState::Ready => true,
+            PackState::Loading(_) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.centered_and_justified(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Loading data…");
+                        });
+                    });
+                });
+                false
+            }
+            PackState::Failed(e) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.centered_and_justified(|ui| {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Failed to load data: {e}"),
+                        );
+                    });
+                });
+                false
+            }
+        }
+    }
 }
 
 impl eframe::App for TerratreeApp {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, storage_keys::UI_STATE, &self.ui_state);
-        eframe::set_value(storage, storage_keys::TREE_ROOT, &self.tree_root);
+        eframe::set_value(storage, storage_keys::JOURNEY, &self.journey);
+        if let Some(trees) = &self.trees {
+            trees.save(storage);
+        }
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-        //     egui::menu::bar(ui, |ui| {
-        //         if cfg!(not(target_arch = "wasm32")) {
-        //             ui.menu_button("File", |ui| {
-        //                 if ui.button("Quit").clicked() {
-        //                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-        //                 }
-        //             });
-        //             ui.add_space(16.0);
-        //         }
-
-        //         egui::widgets::global_theme_preference_buttons(ui);
-        //     });
-        // });
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !self.wait_for_packs(ctx, frame) {
+            return;
+        }
+
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            egui::MenuBar::new().ui(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    pages::tree::file_menu(ui, self);
+
+                    if cfg!(not(target_arch = "wasm32")) {
+                        ui.separator();
+
+                        if ui.button("Quit").clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    }
+                });
+
+                ui.menu_button("Edit", |ui| pages::tree::edit_menu(ui, self));
+
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.ui_state.show_shopping_list, "Shopping list");
+                    ui.checkbox(&mut self.ui_state.journey_mode, "Journey mode");
+                });
+
+                ui.add_space(16.0);
+
+                egui::widgets::global_theme_preference_buttons(ui);
+            });
+        });
 
         pages::tree::ui(ctx, self);
 
         match self.dragging {
             Dragging::None => return,
             Dragging::Item(item) => {
-                if let Some(image) = item.image_location.as_ref() {
+                if let Some(image) = pack::get().image_name(item) {
                     let align = Align2::CENTER_CENTER;
                     let spacing = 0.;
                     let item_size = vec2(32., 32.);
@@ -108,7 +235,7 @@ impl eframe::App for TerratreeApp {
                             .max_rect(item_rect)
                             .layout(egui::Layout::centered_and_justified(egui::Direction::TopDown)),
                     )
-                    .add(egui::Image::new(format!("wiki://{}", image.name)));
+                    .add(egui::Image::new(format!("wiki://{image}")));
                 }
             }
         }
@@ -0,0 +1,376 @@
+//! The canvas trees are drawn on: zoomed with the mouse wheel, panned by dragging the background
+//! and overviewed in a minimap. Nodes are placed at fixed positions, so everything off screen is
+//! skipped without having to draw it first.
+
+use std::{collections::HashSet, hash::Hash};
+
+use egui::{
+    Align2, LayerId, Pos2, Rect, Sense, StrokeKind, TextStyle, UiBuilder, Vec2, emath::TSTransform,
+    vec2,
+};
+use wiki_data::ArchivedItem;
+
+use super::milestone;
+use crate::{
+    app::Dragging,
+    tree::{Edit, ICON_SIZE, Status, milestone::Milestone},
+};
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 2.;
+/// How much a point of scrolling zooms, as a power of e.
+const ZOOM_SPEED: f32 = 0.002;
+/// The most room the minimap takes up.
+const MINIMAP_SIZE: Vec2 = vec2(200., 150.);
+
+/// What drawing a tree on the canvas needs, handed down through milestones and nodes.
+pub struct Canvas<'a> {
+    /// The ui to draw in, in canvas coordinates. Its clip rect is the part that's on screen.
+    pub ui: &'a mut egui::Ui,
+    pub dragging: &'a Dragging,
+    /// Changes to the tree, to apply once it's drawn.
+    pub edits: &'a mut Vec<Edit>,
+    /// The icon of every node with its status, including those off screen, for the minimap.
+    nodes: Vec<(Rect, Status)>,
+    /// Everything that was placed, on screen or not.
+    bounds: Rect,
+}
+
+impl Canvas<'_> {
+    /// The rect around everything placed so far.
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Records a node's icon for the minimap. Returns whether it's on screen and should be drawn.
+    pub fn add_node(&mut self, icon: Rect, status: Status) -> bool {
+        self.nodes.push((icon, status));
+        self.add(icon)
+    }
+
+    /// Records something placed on the canvas. Returns whether it's on screen and should be
+    /// drawn.
+    pub fn add(&mut self, rect: Rect) -> bool {
+        self.bounds = self.bounds.union(rect);
+        self.ui.is_rect_visible(rect)
+    }
+
+    /// Draws a line if any of it is on screen.
+    pub fn line(&mut self, from: Pos2, to: Pos2, stroke: egui::Stroke) {
+        if self
+            .ui
+            .is_rect_visible(Rect::from_two_pos(from, to).expand(stroke.width))
+        {
+            self.ui.painter().line_segment([from, to], stroke);
+        }
+    }
+
+    /// A slot showing `text` to drop the dragged item into, if an item is dragged. Returns the
+    /// item once it's dropped on the slot.
+    pub fn drop_target(
+        &mut self,
+        rect: Rect,
+        text: &str,
+        text_style: TextStyle,
+    ) -> Option<&'static ArchivedItem> {
+        let Dragging::Item(item) = *self.dragging else {
+            return None;
+        };
+
+        if !self.add(rect) {
+            return None;
+        }
+
+        let ui = &mut *self.ui;
+        let res = ui.allocate_rect(rect, Sense::hover());
+        let style = if res.contains_pointer() {
+            ui.visuals().widgets.active
+        } else {
+            ui.visuals().widgets.inactive
+        };
+
+        let rect = rect.shrink(4.);
+        ui.painter().rect(
+            rect,
+            style.corner_radius,
+            style.bg_fill,
+            style.bg_stroke,
+            StrokeKind::Inside,
+        );
+        ui.painter().text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            ui.style().text_styles[&text_style].clone(),
+            style.fg_stroke.color,
+        );
+
+        (ui.ctx().dragged_id().is_none() && res.contains_pointer()).then_some(item)
+    }
+}
+
+/// Where the canvas is looking: how far it's panned from the top left of the tree, and zoomed.
+#[derive(Clone, Copy)]
+struct View {
+    offset: Vec2,
+    zoom: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self { offset: Vec2::ZERO, zoom: 1. }
+    }
+}
+
+impl View {
+    /// From canvas coordinates to the screen, for a canvas shown in `screen`.
+    fn transform(self, screen: Rect) -> TSTransform {
+        TSTransform::new(screen.min.to_vec2() + self.offset, self.zoom)
+    }
+
+    /// Pans while the background is dragged, and zooms around the pointer on scrolling or
+    /// pinching. Scrolling sideways pans instead.
+    fn pan_and_zoom(&mut self, ui: &egui::Ui, res: &egui::Response, screen: Rect) {
+        if res.dragged_by(egui::PointerButton::Primary)
+            || res.dragged_by(egui::PointerButton::Middle)
+        {
+            self.offset += self.zoom * res.drag_delta();
+        }
+
+        let Some(pointer) = ui.input(|i| i.pointer.latest_pos()) else {
+            return;
+        };
+
+        if !res.contains_pointer() {
+            return;
+        }
+
+        let (scroll, zoom) = ui.input(|i| (i.smooth_scroll_delta, i.zoom_delta()));
+
+        self.offset.x += scroll.x;
+
+        let factor = zoom * (scroll.y * ZOOM_SPEED).exp();
+        if factor != 1. {
+            self.zoom_at(screen, pointer, factor);
+        }
+    }
+
+    /// Zooms by `factor`, keeping what's at `pos` on screen in place.
+    fn zoom_at(&mut self, screen: Rect, pos: Pos2, factor: f32) {
+        let on_canvas = self.transform(screen).inverse() * pos;
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset = pos - screen.min - self.zoom * on_canvas.to_vec2();
+    }
+
+    /// Pans to have `pos` on the canvas in the middle of the screen.
+    fn center_on(&mut self, screen: Rect, pos: Pos2) {
+        self.offset = screen.center() - screen.min - self.zoom * pos.to_vec2();
+    }
+
+    /// Zooms and pans to fit all of `bounds` on screen, without zooming in past 100%.
+    fn fit(&mut self, screen: Rect, bounds: Rect) {
+        if !bounds.is_positive() {
+            return;
+        }
+
+        let margin = Vec2::splat(2. * ICON_SIZE);
+        self.zoom = (screen.size() / (bounds.size() + margin))
+            .min_elem()
+            .clamp(MIN_ZOOM, 1.);
+        self.center_on(screen, bounds.center());
+    }
+
+    /// Buttons to fit the tree on screen and reset the zoom, over the minimap, in the bottom
+    /// right of `screen`.
+    fn controls(
+        &mut self,
+        ctx: &egui::Context,
+        id: egui::Id,
+        screen: Rect,
+        nodes: &[(Rect, Status)],
+        bounds: Rect,
+    ) {
+        egui::Area::new(id.with("controls"))
+            .pivot(Align2::RIGHT_BOTTOM)
+            .fixed_pos(screen.right_bottom() - vec2(8., 8.))
+            .constrain_to(screen)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Fit")
+                            .on_hover_text("Zoom out to see the whole tree")
+                            .clicked()
+                        {
+                            self.fit(screen, bounds);
+                        }
+
+                        if ui.button("100%").on_hover_text("Reset the zoom").clicked() {
+                            self.zoom_at(screen, screen.center(), 1. / self.zoom);
+                        }
+
+                        ui.weak(format!("{:.0}%", self.zoom * 100.));
+                    });
+
+                    self.minimap(ui, screen, nodes, bounds);
+                });
+            });
+    }
+
+    /// Draws every node as a dot in its status's color, with a frame around what's on screen.
+    /// Clicking or dragging on it moves the view there.
+    fn minimap(&mut self, ui: &mut egui::Ui, screen: Rect, nodes: &[(Rect, Status)], bounds: Rect) {
+        if nodes.is_empty() {
+            return;
+        }
+
+        let scale = (MINIMAP_SIZE / bounds.size()).min_elem().min(0.25);
+        let (res, painter) = ui.allocate_painter(bounds.size() * scale, Sense::click_and_drag());
+        let to_map = |rect: Rect| {
+            Rect::from_min_max(
+                res.rect.min + (rect.min - bounds.min) * scale,
+                res.rect.min + (rect.max - bounds.min) * scale,
+            )
+        };
+
+        painter.rect_filled(res.rect, 2., ui.visuals().extreme_bg_color);
+
+        // Nodes that start on the same pixel are drawn once, so huge trees don't draw a rect for
+        // each of thousands of nodes.
+        let planned = ui.visuals().weak_text_color();
+        let mut drawn = HashSet::new();
+        for &(icon, status) in nodes {
+            let rect = to_map(icon);
+            if drawn.insert((rect.min.x as i32, rect.min.y as i32)) {
+                painter.rect_filled(rect, 0., status.color().unwrap_or(planned));
+            }
+        }
+
+        let on_screen = self.transform(screen).inverse() * screen;
+        painter.rect_stroke(
+            to_map(on_screen).intersect(res.rect),
+            0.,
+            ui.visuals().selection.stroke,
+            StrokeKind::Inside,
+        );
+
+        if let Some(pos) = res.interact_pointer_pos() {
+            self.center_on(screen, bounds.min + (pos - res.rect.min) / scale);
+        }
+    }
+}
+
+/// Draws the milestones of a tree on a canvas filling the rest of `ui`. Each `id_salt` keeps its
+/// own view. Renaming a milestone changes it right away, other changes are pushed to `edits`.
+pub fn ui(
+    ui: &mut egui::Ui,
+    id_salt: impl Hash,
+    milestones: &mut [Milestone],
+    dragging: &Dragging,
+    edits: &mut Vec<Edit>,
+) {
+    let id = ui.id().with(id_salt);
+    let (screen, _) = ui.allocate_exact_size(ui.available_size_before_wrap(), Sense::hover());
+    let mut view = ui.data(|d| d.get_temp::<View>(id)).unwrap_or_default();
+
+    // The tree goes on its own layer above the panel, drawn through the view's transform.
+    let layer = LayerId::new(ui.layer_id().order, id.with("layer"));
+    ui.ctx().set_sublayer(ui.layer_id(), layer);
+
+    let on_screen = view.transform(screen).inverse() * screen;
+    let mut canvas_ui = ui.new_child(
+        UiBuilder::new()
+            .layer_id(layer)
+            .max_rect(on_screen)
+            .sense(Sense::click_and_drag()),
+    );
+
+    view.pan_and_zoom(&canvas_ui, &canvas_ui.response(), screen);
+
+    let transform = view.transform(screen);
+    canvas_ui.set_clip_rect(transform.inverse() * screen);
+    ui.ctx().set_transform_layer(layer, transform);
+
+    let mut canvas = Canvas {
+        ui: &mut canvas_ui,
+        dragging,
+        edits,
+        nodes: Vec::new(),
+        bounds: Rect::NOTHING,
+    };
+    milestone::ui(milestones, &mut canvas);
+    let Canvas { nodes, bounds, .. } = canvas;
+
+    // So dragging anywhere on the background pans, even past the tree.
+    canvas_ui.expand_to_include_rect(transform.inverse() * screen);
+
+    view.controls(ui.ctx(), id, screen, &nodes, bounds);
+
+    ui.ctx().set_transform_layer(layer, view.transform(screen));
+    ui.data_mut(|d| d.insert_temp(id, view));
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::pos2;
+
+    use super::*;
+
+    const SCREEN: Rect = Rect::from_min_max(pos2(100., 50.), pos2(900., 650.));
+
+    fn assert_near(a: Pos2, b: Pos2) {
+        assert!((a - b).length() < 1e-3, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn zoom_at() {
+        let mut view = View::default();
+        let pointer = pos2(300., 200.);
+        let on_canvas = view.transform(SCREEN).inverse() * pointer;
+
+        view.zoom_at(SCREEN, pointer, 0.5);
+        assert_eq!(view.zoom, 0.5);
+        assert_near(view.transform(SCREEN) * on_canvas, pointer);
+
+        // Clamped, with what's under the pointer still staying in place.
+        view.zoom_at(SCREEN, pointer, 100.);
+        assert_eq!(view.zoom, MAX_ZOOM);
+        assert_near(view.transform(SCREEN) * on_canvas, pointer);
+
+        view.zoom_at(SCREEN, pointer, 0.);
+        assert_eq!(view.zoom, MIN_ZOOM);
+        assert_near(view.transform(SCREEN) * on_canvas, pointer);
+    }
+
+    #[test]
+    fn center_on() {
+        let mut view = View { offset: vec2(40., -70.), zoom: 0.5 };
+        view.center_on(SCREEN, pos2(1000., 400.));
+
+        assert_eq!(view.zoom, 0.5);
+        assert_near(view.transform(SCREEN) * pos2(1000., 400.), SCREEN.center());
+    }
+
+    #[test]
+    fn fit() {
+        let mut view = View::default();
+
+        // Zooms out until the tree and a margin fit, and centers it.
+        let bounds = Rect::from_min_size(pos2(0., 0.), vec2(3000., 1000.));
+        view.fit(SCREEN, bounds);
+        assert_eq!(view.zoom, SCREEN.width() / (bounds.width() + 2. * ICON_SIZE));
+        assert!(SCREEN.contains_rect(view.transform(SCREEN) * bounds));
+        assert_near(view.transform(SCREEN) * bounds.center(), SCREEN.center());
+
+        // But doesn't zoom in past 100%, or out past the least zoom.
+        view.fit(SCREEN, Rect::from_min_size(pos2(10., 10.), vec2(50., 50.)));
+        assert_eq!(view.zoom, 1.);
+        view.fit(SCREEN, Rect::from_min_size(pos2(0., 0.), vec2(1e6, 10.)));
+        assert_eq!(view.zoom, MIN_ZOOM);
+
+        // Nothing placed, nothing to fit.
+        let before = view.offset;
+        view.fit(SCREEN, Rect::NOTHING);
+        assert_eq!((view.offset, view.zoom), (before, MIN_ZOOM));
+    }
+}
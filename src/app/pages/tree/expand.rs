@@ -0,0 +1,266 @@
+use std::collections::HashSet;
+
+use wiki_data::{ArchivedItem, ArchivedRecipe};
+
+use crate::{
+    pack::{self, RecipeSource},
+    tree::{Edit, Node, Op, Path},
+};
+
+/// How many recipe levels "Expand ingredients" will descend below the expanded node.
+const MAX_DEPTH: usize = 8;
+
+impl Node {
+    /// Adds the ingredients of `recipe` to this node's children, each expanded into its own
+    /// crafting tree down to raw materials with recipes picked from `recipes`. Ingredients that
+    /// are already children only have their amount updated, and other children are left alone.
+    fn expand_with(&mut self, recipe: &ArchivedRecipe, recipes: &impl RecipeSource) {
+        let mut path = HashSet::from([self.item.name.as_str()]);
+        self.expand_recipe(recipe, recipes, &mut path, MAX_DEPTH);
+    }
+
+    /// The edit that expands the node at `path` with `recipe`.
+    pub fn expand(
+        &self,
+        path: &Path,
+        recipe: &ArchivedRecipe,
+        recipes: &impl RecipeSource,
+    ) -> Edit {
+        let mut expanded = self.clone();
+        expanded.expand_with(recipe, recipes);
+
+        vec![Op::SetChildren {
+            at: path.clone(),
+            old: self.children.clone(),
+            new: expanded.children,
+        }]
+    }
+
+    fn expand_recipe(
+        &mut self,
+        recipe: &ArchivedRecipe,
+        recipes: &impl RecipeSource,
+        path: &mut HashSet<&'static str>,
+        depth: usize,
+    ) {
+        let crafts = self.amount.div_ceil(recipe.amount.to_native().max(1));
+
+        for ingredient in recipe.ingredients.iter() {
+            let Some(item) = ingredient_item(&ingredient.name) else {
+                log::warn!(
+                    "unknown ingredient {:?} in recipe for {}",
+                    ingredient.name,
+                    recipe.result
+                );
+                continue;
+            };
+
+            let amount = crafts * ingredient.amount.to_native();
+
+            if let Some(child) = self.children.iter_mut().find(|c| c.item.name == item.name) {
+                child.amount = amount;
+                continue;
+            }
+
+            let mut child = Node::from_item(item);
+            child.amount = amount;
+
+            if depth > 1 && path.insert(item.name.as_str()) {
+                if let Some(recipe) = preferred_recipe(recipes, &item.name, path) {
+                    child.expand_recipe(recipe, recipes, path, depth - 1);
+                }
+
+                path.remove(item.name.as_str());
+            }
+
+            self.children.push(child);
+        }
+    }
+}
+
+/// Picks the recipe used when expanding an ingredient automatically. Recipes that would loop back
+/// into an item already on the current path are never chosen, and simpler recipes are preferred.
+fn preferred_recipe(
+    recipes: &impl RecipeSource,
+    name: &str,
+    path: &HashSet<&str>,
+) -> Option<&'static ArchivedRecipe> {
+    recipes
+        .producing(name)
+        .filter(|recipe| {
+            recipe.ingredients.iter().all(|i| {
+                ingredient_item(&i.name).is_none_or(|item| !path.contains(item.name.as_str()))
+            })
+        })
+        .min_by_key(|recipe| (recipe.ingredients.len(), recipe.stations.len()))
+}
+
+/// Resolves an ingredient to an item. Recipe groups like "Any Wood" resolve to their namesake
+/// item.
+fn ingredient_item(name: &str) -> Option<&'static ArchivedItem> {
+    let packs = pack::get();
+
+    packs.item_by_name(name).or_else(|| {
+        let group = name.strip_prefix("Any ")?;
+        packs
+            .item_by_name(group)
+            .or_else(|| packs.item_by_name(&format!("{group} Block")))
+    })
+}
+
+/// Short description of a recipe for picking between alternatives, like
+/// "10 Wood, 2 Iron Bar @ Work Bench".
+pub fn recipe_summary(recipe: &ArchivedRecipe) -> String {
+    let ingredients = recipe
+        .ingredients
+        .iter()
+        .map(|i| format!("{} {}", i.amount, i.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let requirements = recipe
+        .stations
+        .iter()
+        .map(|s| s.to_string())
+        .chain(recipe.conditions.iter().map(|c| c.to_string()))
+        .collect::<Vec<_>>();
+
+    if requirements.is_empty() {
+        ingredients
+    } else {
+        format!("{ingredients} @ {}", requirements.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::pages::tree::test_support::{graph, recipe},
+        tree::Status,
+    };
+
+    fn children(node: &Node) -> Vec<(&str, u32)> {
+        node.children
+            .iter()
+            .map(|c| (c.item.name.as_str(), c.amount))
+            .collect()
+    }
+
+    #[test]
+    fn ingredient_items() {
+        pack::load_embedded();
+
+        assert_eq!(ingredient_item("Wood").unwrap().name, "Wood");
+        assert_eq!(ingredient_item("Any Wood").unwrap().name, "Wood");
+        assert_eq!(ingredient_item("Any Sand").unwrap().name, "Sand Block");
+        assert!(ingredient_item("Any Nonsense").is_none());
+    }
+
+    #[test]
+    fn prefers_simple_recipes_off_the_path() {
+        let recipes = graph(vec![
+            recipe("Torch", 3, &[("Gel", 1), ("Wood", 1)], &[]),
+            recipe("Torch", 1, &[("Copper Bar", 1)], &[]),
+            recipe("Torch", 1, &[("Iron Bar", 1), ("Gel", 1), ("Wood", 1)], &[]),
+        ]);
+
+        let simplest = preferred_recipe(&recipes, "Torch", &HashSet::new()).unwrap();
+        assert_eq!(simplest.ingredients[0].name, "Copper Bar");
+
+        let avoiding = preferred_recipe(&recipes, "Torch", &HashSet::from(["Copper Bar"])).unwrap();
+        assert_eq!(avoiding.ingredients.len(), 2);
+
+        assert!(
+            preferred_recipe(&recipes, "Torch", &HashSet::from(["Wood", "Copper Bar"])).is_none()
+        );
+        assert!(preferred_recipe(&recipes, "Wood", &HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn expands_down_to_raw_materials() {
+        let recipes = graph(vec![
+            recipe("Copper Pickaxe", 1, &[("Copper Bar", 12), ("Any Wood", 4)], &[]),
+            recipe("Copper Bar", 1, &[("Copper Ore", 3)], &[]),
+        ]);
+
+        let mut root = Node::from_name("Copper Pickaxe").unwrap();
+        root.amount = 2;
+
+        let mut wood = Node::from_name("Wood").unwrap();
+        wood.amount = 1;
+        wood.status = Status::Obtained;
+        root.children.push(wood);
+
+        root.expand_with(recipes.producing("Copper Pickaxe").next().unwrap(), &recipes);
+
+        assert_eq!(children(&root), [("Wood", 8), ("Copper Bar", 24)]);
+        assert_eq!(root.children[0].status, Status::Obtained);
+        assert_eq!(children(&root.children[1]), [("Copper Ore", 72)]);
+    }
+
+    #[test]
+    fn rounds_up_to_whole_crafts() {
+        let recipes = graph(vec![recipe("Torch", 3, &[("Gel", 1), ("Wood", 1)], &[])]);
+
+        let mut root = Node::from_name("Torch").unwrap();
+        root.amount = 4;
+        root.expand_with(recipes.producing("Torch").next().unwrap(), &recipes);
+
+        assert_eq!(children(&root), [("Gel", 2), ("Wood", 2)]);
+    }
+
+    #[test]
+    fn stops_at_cycles() {
+        let recipes = graph(vec![
+            recipe("Copper Pickaxe", 1, &[("Copper Bar", 12)], &[]),
+            recipe("Copper Bar", 1, &[("Copper Ore", 3)], &[]),
+            recipe("Copper Ore", 1, &[("Copper Bar", 1)], &[]),
+            recipe("Copper Ore", 1, &[("Copper Pickaxe", 1)], &[]),
+        ]);
+
+        let mut root = Node::from_name("Copper Pickaxe").unwrap();
+        root.expand_with(recipes.producing("Copper Pickaxe").next().unwrap(), &recipes);
+
+        let ore = &root.children[0].children[0];
+        assert_eq!(ore.item.name, "Copper Ore");
+        assert!(ore.children.is_empty());
+    }
+
+    #[test]
+    fn stops_at_max_depth() {
+        let names = [
+            "Wood",
+            "Gel",
+            "Torch",
+            "Copper Ore",
+            "Copper Bar",
+            "Iron Ore",
+            "Iron Bar",
+            "Stone Block",
+            "Sand Block",
+            "Glass",
+            "Bottle",
+            "Mushroom",
+        ];
+        let recipes = graph(
+            names
+                .windows(2)
+                .map(|pair| recipe(pair[0], 1, &[(pair[1], 1)], &[]))
+                .collect::<Vec<_>>(),
+        );
+
+        let mut root = Node::from_name("Wood").unwrap();
+        root.expand_with(recipes.producing("Wood").next().unwrap(), &recipes);
+
+        let mut depth = 0;
+        let mut node = &root;
+        while let Some(child) = node.children.first() {
+            depth += 1;
+            node = child;
+        }
+
+        assert_eq!(depth, MAX_DEPTH);
+        assert_eq!(node.item.name, names[MAX_DEPTH]);
+    }
+}
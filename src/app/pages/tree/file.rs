@@ -0,0 +1,206 @@
+//! Saving the shown tree to a tree file, and opening tree files as new trees.
+
+use std::{collections::BTreeSet, sync::mpsc};
+
+use crate::{
+    TerratreeApp,
+    app::files,
+    tree::{
+        file::{Format, read, write},
+        render,
+    },
+};
+
+const FILTERS: &[files::Filter<'static>] =
+    &[("Tree (TOML)", &["toml"]), ("Tree (JSON)", &["json"])];
+
+const IMAGE_FILTERS: &[files::Filter<'static>] =
+    &[("PNG image", &["png"]), ("SVG image", &["svg"])];
+
+/// Trees being saved or opened, and what to tell about the last save or open.
+pub struct TreeFiles {
+    tx: mpsc::Sender<Finished>,
+    rx: mpsc::Receiver<Finished>,
+    report: Option<Report>,
+}
+
+/// A save or open that finished in the background.
+enum Finished {
+    Saved(anyhow::Result<()>, &'static str),
+    Opened(anyhow::Result<files::OpenedFile>),
+}
+
+enum Report {
+    Missing {
+        tree: String,
+        items: BTreeSet<String>,
+    },
+    /// Items of the stored trees that were left out when loading them.
+    Stored(BTreeSet<String>),
+    Failed(String),
+}
+
+impl Default for TreeFiles {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self { tx, rx, report: None }
+    }
+}
+
+impl TreeFiles {
+    /// Tells that items of the stored trees were left out, if any were.
+    pub fn left_out_of_storage(&mut self, items: &BTreeSet<String>) {
+        if !items.is_empty() {
+            self.report = Some(Report::Stored(items.clone()));
+        }
+    }
+}
+
+/// File menu entries to save the shown tree to a file and open one as a new tree.
+pub fn menu(ui: &mut egui::Ui, app: &mut TerratreeApp) {
+    let Some(trees) = &app.trees else {
+        return;
+    };
+
+    let finished = |ui: &egui::Ui| {
+        let tx = app.tree_files.tx.clone();
+        let ctx = ui.ctx().clone();
+
+        move |finished| {
+            tx.send(finished).ok();
+            ctx.request_repaint();
+        }
+    };
+
+    if ui.button("Save as…").clicked() {
+        let tree = trees.active().clone();
+        let file_name = format!("{}.toml", file_stem(&tree.name));
+        let finished = finished(ui);
+
+        files::save(
+            &file_name,
+            FILTERS,
+            move |name| write(&tree, Format::of(name)).map(String::into_bytes),
+            move |saved| finished(Finished::Saved(saved, "Couldn't save the tree")),
+        );
+    }
+
+    if ui.button("Export image…").clicked() {
+        let tree = trees.active().clone();
+        let file_name = format!("{}.png", file_stem(&tree.name));
+        let finished = finished(ui);
+
+        files::save(
+            &file_name,
+            IMAGE_FILTERS,
+            move |name| render::export(&tree, name),
+            move |saved| finished(Finished::Saved(saved, "Couldn't export the tree")),
+        );
+    }
+
+    if ui.button("Open…").clicked() {
+        let finished = finished(ui);
+        files::open(FILTERS, move |file| finished(Finished::Opened(file)));
+    }
+}
+
+/// A file name for a tree, without the extension.
+fn file_stem(name: &str) -> String {
+    name.replace(['/', '\\'], "-")
+}
+
+/// Adds trees that finished opening, and shows what went wrong with the last save or open.
+pub fn ui(ctx: &egui::Context, app: &mut TerratreeApp) {
+    let files = &mut app.tree_files;
+
+    while let Ok(finished) = files.rx.try_recv() {
+        let file = match finished {
+            Finished::Saved(Ok(()), _) => continue,
+            Finished::Saved(Err(e), failed) => {
+                files.report = Some(Report::Failed(format!("{failed}: {e}")));
+                continue;
+            }
+            Finished::Opened(file) => file,
+        };
+
+        let opened = file.and_then(|file| {
+            read(&file.contents, Format::of(&file.name))
+                .map_err(|e| anyhow::anyhow!("{}: {e}", file.name))
+        });
+
+        match (opened, &mut app.trees) {
+            (Ok((tree, missing)), Some(trees)) => {
+                if !missing.is_empty() {
+                    files.report = Some(Report::Missing {
+                        tree: tree.name.clone(),
+                        items: missing,
+                    });
+                }
+                trees.add(tree);
+            }
+            (Ok(_), None) => {}
+            (Err(e), _) => files.report = Some(Report::Failed(format!("Couldn't open: {e}"))),
+        }
+    }
+
+    let Some(report) = &files.report else {
+        return;
+    };
+
+    let mut open = true;
+    let mut forget_missing = false;
+
+    egui::Window::new("Tree file")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .open(&mut open)
+        .show(ctx, |ui| match report {
+            Report::Missing { tree, items } => {
+                ui.label(format!(
+                    "These items of {tree} aren't in the loaded data, so they were left out \
+                     along with what branches off them:"
+                ));
+
+                missing_list(ui, items);
+            }
+            Report::Stored(items) => {
+                ui.label(
+                    "These items of your trees aren't in the loaded data, maybe because a data \
+                     pack wasn't loaded this time. They were left out along with what branches \
+                     off them:",
+                );
+                missing_list(ui, items);
+                ui.label(
+                    "Your trees won't be saved until then, so they're all back once the data \
+                     packs are. Changes made now are lost, unless you save without the items.",
+                );
+
+                if ui.button("Save without them").clicked() {
+                    forget_missing = true;
+                }
+            }
+            Report::Failed(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+        });
+
+    if forget_missing && let Some(trees) = &mut app.trees {
+        trees.forget_missing();
+        open = false;
+    }
+
+    if !open {
+        app.tree_files.report = None;
+    }
+}
+
+fn missing_list(ui: &mut egui::Ui, items: &BTreeSet<String>) {
+    egui::ScrollArea::vertical()
+        .max_height(200.)
+        .show(ui, |ui| {
+            for item in items {
+                ui.label(format!("• {item}"));
+            }
+        });
+}
@@ -0,0 +1,35 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+use crate::tree::Tree;
+
+pub const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+pub const REDO: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+/// Undoes or redoes an edit of `tree` when their shortcut is pressed, unless a text field has
+/// the keyboard.
+pub fn shortcuts(ctx: &egui::Context, tree: &mut Tree) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    // Redo first, since the undo shortcut also matches with Shift held.
+    if ctx.input_mut(|i| i.consume_shortcut(&REDO)) {
+        tree.redo();
+    } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO)) {
+        tree.undo();
+    }
+}
+
+/// Undo and redo entries for the Edit menu.
+pub fn menu(ui: &mut egui::Ui, tree: &mut Tree) {
+    let undo = egui::Button::new("Undo").shortcut_text(ui.ctx().format_shortcut(&UNDO));
+    if ui.add_enabled(tree.history.can_undo(), undo).clicked() {
+        tree.undo();
+    }
+
+    let redo = egui::Button::new("Redo").shortcut_text(ui.ctx().format_shortcut(&REDO));
+    if ui.add_enabled(tree.history.can_redo(), redo).clicked() {
+        tree.redo();
+    }
+}
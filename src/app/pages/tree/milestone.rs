@@ -0,0 +1,136 @@
+use egui::{Color32, Pos2, Rect, Sense, TextStyle, Vec2, pos2, vec2};
+
+use super::canvas::Canvas;
+use crate::{
+    app::Dragging,
+    tree::{
+        ICON_SIZE, INDENT, MILESTONE_ICON_SIZE, MILESTONE_SPACING, Node, Op, Path, SPACING,
+        milestone::Milestone,
+    },
+};
+
+impl Milestone {
+    /// Draws the title of the milestone at `index` at `pos` and its roots below it, with a drop
+    /// target to add roots while an item is dragged. Returns where the next thing goes below it.
+    fn ui(&mut self, index: usize, pos: Pos2, canvas: &mut Canvas<'_>) -> f32 {
+        let ui = &mut *canvas.ui;
+        let title = ui.painter().layout_no_wrap(
+            self.title.clone(),
+            ui.style().text_styles[&TextStyle::Heading].clone(),
+            ui.visuals().text_color(),
+        );
+
+        let icon = Rect::from_min_size(pos, Vec2::splat(MILESTONE_ICON_SIZE));
+        let title_left = match self.image {
+            Some(_) => icon.right() + SPACING * 2.,
+            None => icon.left(),
+        };
+        let title_pos = pos2(title_left, icon.center().y - title.size().y / 2.);
+        let header = Rect::from_min_max(pos, pos2(title_left + title.size().x, icon.bottom()));
+
+        if canvas.add(header) {
+            let ui = &mut *canvas.ui;
+
+            if let Some(image) = &self.image {
+                ui.put(
+                    icon,
+                    egui::Image::new(format!("wiki://{image}")).fit_to_exact_size(icon.size()),
+                );
+            }
+
+            ui.painter()
+                .galley(title_pos, title, ui.visuals().text_color());
+
+            let res = ui.interact(header, ui.id().with(("milestone", index)), Sense::click());
+            res.context_menu(|ui| {
+                let res = ui.text_edit_singleline(&mut self.title);
+                if ui.memory(|m| m.focused().is_none()) {
+                    res.request_focus();
+                }
+                if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    ui.close();
+                }
+
+                if ui.button("Delete").clicked() {
+                    canvas
+                        .edits
+                        .push(vec![Op::RemoveMilestone { index, milestone: self.clone() }]);
+                }
+            });
+        }
+
+        let x = pos.x + INDENT;
+        let mut y = header.bottom() + SPACING;
+
+        for (root_index, root) in self.roots.iter().enumerate() {
+            let path = Path::root(index, root_index);
+            let (res, bottom, _) = root.ui(&path, pos2(x, y), canvas);
+
+            if let Some(res) = res {
+                res.context_menu(|ui| root.context_menu(&path, ui, canvas.edits));
+
+                if res.clicked_by(egui::PointerButton::Middle) {
+                    canvas
+                        .edits
+                        .push(vec![Op::Remove { at: path, node: root.clone() }]);
+                }
+            }
+
+            y = bottom;
+        }
+
+        if matches!(canvas.dragging, Dragging::Item(_)) {
+            let target = Rect::from_min_size(pos2(x, y), Vec2::splat(ICON_SIZE));
+
+            if let Some(item) = canvas.drop_target(target, "+", TextStyle::Heading) {
+                canvas.edits.push(vec![Op::Insert {
+                    at: Path::root(index, self.roots.len()),
+                    node: Node::from_item(item),
+                }]);
+            }
+
+            y = target.bottom() + SPACING;
+        } else if self.roots.is_empty() {
+            let ui = &mut *canvas.ui;
+            let hint = ui.painter().layout_no_wrap(
+                "Drag items here".to_owned(),
+                ui.style().text_styles[&TextStyle::Body].clone(),
+                ui.visuals().weak_text_color(),
+            );
+            let rect = Rect::from_min_size(pos2(x, y), hint.size());
+
+            if canvas.add(rect) {
+                canvas
+                    .ui
+                    .painter()
+                    .galley(rect.min, hint, Color32::PLACEHOLDER);
+            }
+
+            y = rect.bottom() + SPACING;
+        }
+
+        y
+    }
+}
+
+/// Draws all milestones of a tree from the top left of the canvas, followed by a drop target
+/// that creates a new milestone for the dragged item. Renaming a milestone changes it right away,
+/// other changes are pushed to the canvas.
+pub fn ui(milestones: &mut [Milestone], canvas: &mut Canvas<'_>) {
+    let mut y = 0.;
+
+    for (index, milestone) in milestones.iter_mut().enumerate() {
+        y = milestone.ui(index, pos2(0., y), canvas) + MILESTONE_SPACING;
+    }
+
+    // As wide as the tree, so it's easy to hit.
+    let width = canvas.bounds().right().max(6. * ICON_SIZE);
+    let target = Rect::from_min_size(pos2(0., y), vec2(width, ICON_SIZE));
+
+    if let Some(item) = canvas.drop_target(target, "Create milestone", TextStyle::Heading) {
+        canvas.edits.push(vec![Op::InsertMilestone {
+            index: milestones.len(),
+            milestone: Milestone::from_item(item),
+        }]);
+    }
+}
@@ -1,186 +1,384 @@
 use egui::{
-    Align2, Color32, Image, Layout, OpenUrl, Sense, StrokeKind, UiBuilder, Widget as _,
-    epaint::Hsva, pos2, vec2,
-};
-use serde::{Deserialize, Serialize};
-use wiki_data::{
-    ITEMS, Item,
-    item::{Rarity, RarityColor},
+    Align2, Image, Layout, OpenUrl, Pos2, Rect, Sense, TextStyle, UiBuilder, Vec2, pos2, vec2,
 };
+use wiki_data::{ArchivedItem, DataPack, item::Coins};
 
-use crate::{TerratreeApp, app::Dragging};
+use crate::{
+    TerratreeApp,
+    app::{Dragging, journey::Journey, widgets},
+    pack, tdata,
+    tree::{Edit, ICON_SIZE, Node, Op, Path, SPACING, Status},
+};
 
-mod node_by_name;
+mod canvas;
+mod expand;
+mod file;
+mod history;
+mod milestone;
+mod share;
+mod shopping_list;
+mod status;
+#[cfg(test)]
+mod test_support;
+mod trees;
+
+pub use file::TreeFiles;
+pub use share::SharedTree;
+pub use shopping_list::CachedShoppingList;
+pub use trees::Trees;
+
+use canvas::Canvas;
 
-// #[derive(Serialize, Deserialize)]
-// pub struct Milestone {
-//     pub image: String,
-//     pub title: String,
-//     #[serde(with = "node_by_name")]
-//     pub roots: Vec<Node>,
-// }
+impl Node {
+    /// Draws the node at `path` with its icon at `pos` and everything branching off it below,
+    /// skipping what's off screen. Returns the response of its icon if it was drawn, where the
+    /// next thing goes below it, and the [`Self::chain_counts`], added up while drawing so the
+    /// progress of every node takes a single pass. Changes to the tree are pushed to the canvas
+    /// rather than made right away, so they can be undone.
+    pub fn ui(
+        &self,
+        path: &Path,
+        pos: Pos2,
+        canvas: &mut Canvas<'_>,
+    ) -> (Option<egui::Response>, f32, (u32, u32)) {
+        let icon = Rect::from_min_size(pos, Vec2::splat(ICON_SIZE));
+        let res = canvas
+            .add_node(icon, self.status)
+            .then(|| self.icon_ui(path, icon, canvas));
+
+        let is_dragging_item = matches!(canvas.dragging, Dragging::Item(_));
+        let inactive = canvas.ui.visuals().widgets.inactive.fg_stroke;
+
+        let main_line_x = icon.center().x;
+        let mut y = icon.bottom() + SPACING;
+        let mut last_line_y = None;
+        let mut child_counts = Vec::with_capacity(self.children.len());
+
+        for (index, child) in self.children.iter().enumerate() {
+            let child_path = path.child(index);
+            let child_pos = pos2(icon.right(), y);
+            let (res, bottom, counts) = child.ui(&child_path, child_pos, canvas);
+            child_counts.push(counts);
+
+            let line_y = child_pos.y + ICON_SIZE / 2.;
+            canvas.line(pos2(child_pos.x, line_y), pos2(main_line_x, line_y), inactive);
+            last_line_y = Some(line_y);
+
+            if let Some(res) = res {
+                res.context_menu(|ui| {
+                    child.context_menu(&child_path, ui, canvas.edits);
+
+                    if ui
+                        .add_enabled(index > 0, egui::Button::new("To top"))
+                        .clicked()
+                    {
+                        canvas.edits.push(vec![
+                            Op::Remove {
+                                at: child_path.clone(),
+                                node: child.clone(),
+                            },
+                            Op::Insert {
+                                at: path.child(0),
+                                node: child.clone(),
+                            },
+                        ]);
+                    }
+                });
 
-#[derive(Serialize, Deserialize)]
-pub struct Node {
-    #[serde(with = "node_by_name")]
-    pub item: &'static Item,
-    pub next: Option<Box<Node>>,
-    pub children: Vec<Node>,
-}
+                if res.clicked_by(egui::PointerButton::Middle) {
+                    canvas
+                        .edits
+                        .push(vec![Op::Remove { at: child_path, node: child.clone() }]);
+                }
+            }
 
-impl Node {
-    pub fn from_item(item: &'static Item) -> Self {
-        Self {
-            item,
-            next: None,
-            children: Vec::new(),
+            y = bottom;
         }
-    }
-
-    pub fn from_name(name: &'static str) -> Option<Self> {
-        ITEMS
-            .iter()
-            .find(|item| item.name == name)
-            .map(Self::from_item)
-    }
 
-    pub fn ui(&mut self, dragging: &mut Dragging, ui: &mut egui::Ui) -> egui::Response {
-        let img = self.item.image_location.as_ref().unwrap();
+        let mut counts = branch_counts(self.status, child_counts);
+        if let Some(res) = &res
+            && !self.children.is_empty()
+            && let Some(progress) = fraction(counts)
+        {
+            status::paint_progress(canvas.ui.painter(), res.rect, progress);
+        }
 
-        let image_res = Image::new(format!("wiki://{}", img.name))
-            .fit_to_exact_size(vec2(48., 48.))
-            .ui(ui);
+        if is_dragging_item {
+            let target = Rect::from_min_size(pos2(icon.right(), y), icon.size());
+            let line_y = target.center().y;
+            canvas.line(target.left_center(), pos2(main_line_x, line_y), inactive);
+            last_line_y = Some(line_y);
+
+            if let Some(item) = canvas.drop_target(target, "+", TextStyle::Heading) {
+                canvas.edits.push(vec![Op::Insert {
+                    at: path.child(self.children.len()),
+                    node: Node::from_item(item),
+                }]);
+            }
 
-        if let Dragging::Item(item) = dragging
-            && ui.ctx().dragged_id().is_none()
-            && image_res.contains_pointer()
-        {
-            self.children.push(Node::from_item(item));
+            y = target.bottom() + SPACING;
         }
 
-        let is_dragging_item = matches!(dragging, Dragging::Item(_));
+        if self.next.is_some() || is_dragging_item {
+            let active = canvas.ui.visuals().widgets.active.fg_stroke;
+            canvas.line(icon.center_bottom(), pos2(main_line_x, y), active);
+        } else if let Some(line_y) = last_line_y {
+            canvas.line(icon.center_bottom(), pos2(main_line_x, line_y), inactive);
+        }
 
-        let main_line_x = image_res.rect.center().x;
-        let mut last_line_pos = None;
+        if let Some(next) = &self.next {
+            let next_path = path.next();
+            let (res, bottom, next_counts) = next.ui(&next_path, pos2(icon.left(), y), canvas);
+            counts.0 += next_counts.0;
+            counts.1 += next_counts.1;
 
-        let child_rect = {
-            let mut child_rect = ui.available_rect_before_wrap();
-            child_rect.set_left(child_rect.left() + 48.);
+            if let Some(res) = res {
+                res.context_menu(|ui| next.context_menu(&next_path, ui, canvas.edits));
 
-            let mut ui = ui.new_child(
-                UiBuilder::new()
-                    .layout(Layout::top_down(egui::Align::LEFT))
-                    .max_rect(child_rect),
-            );
-            let ui = &mut ui;
+                if res.clicked_by(egui::PointerButton::Middle) {
+                    canvas.edits.push(vec![Op::Remove {
+                        at: next_path,
+                        node: (**next).clone(),
+                    }]);
+                }
+            }
 
-            let mut move_to_top = None;
-            let mut index = 0;
-            self.children.retain_mut(|child| {
-                let res = child.ui(dragging, ui);
+            y = bottom;
+        } else if is_dragging_item {
+            let target = Rect::from_min_size(pos2(icon.left(), y), icon.size());
 
-                let pos = pos2(main_line_x, res.rect.center().y);
-                last_line_pos = Some(pos);
-                ui.painter().line_segment(
-                    [res.rect.left_center(), pos],
-                    ui.visuals().widgets.inactive.fg_stroke,
-                );
+            if let Some(item) = canvas.drop_target(target, "+", TextStyle::Heading) {
+                canvas.edits.push(vec![Op::Insert {
+                    at: path.next(),
+                    node: Node::from_item(item),
+                }]);
+            }
 
-                res.context_menu(|ui| {
-                    if ui.button("To top").clicked() {
-                        move_to_top = Some(index);
-                    }
-                });
+            y = target.bottom() + SPACING;
+        }
 
-                index += 1;
-                !res.clicked_by(egui::PointerButton::Middle)
-            });
+        (res, y, counts)
+    }
 
-            if let Some(i) = move_to_top {
-                let child = self.children.remove(i);
-                self.children.insert(0, child);
+    /// Draws the icon of this node in `rect`, with its status, progress and amount, and handles
+    /// the keys to set its status and dropping items on it.
+    fn icon_ui(&self, path: &Path, rect: Rect, canvas: &mut Canvas<'_>) -> egui::Response {
+        let ui = &mut *canvas.ui;
+
+        let image_res = match pack::get().image_name(self.item) {
+            Some(img) => ui.put(
+                rect,
+                Image::new(format!("wiki://{img}"))
+                    .fit_to_exact_size(rect.size())
+                    .tint(self.status.tint()),
+            ),
+            None => {
+                paint_missing_image(ui, rect, &self.item.name);
+                ui.allocate_rect(rect, Sense::hover())
             }
+        }
+        .on_hover_ui(|ui| widgets::tooltip::ui(ui, self.item));
 
-            if is_dragging_item {
-                let (res, item) = item_drag_target(ui, dragging);
-
-                ui.painter().line_segment(
-                    [res.rect.left_center(), pos2(main_line_x, res.rect.center().y)],
-                    ui.visuals().widgets.inactive.fg_stroke,
-                );
+        // Not while typing, so typing in a text field with the pointer over a node doesn't change
+        // its status.
+        if image_res.hovered() && !ui.ctx().wants_keyboard_input() {
+            let status = ui.input(|i| {
+                let mut status = i.key_pressed(egui::Key::Space).then(|| self.status.next());
 
-                if let Some(item) = item {
-                    self.children.push(Node::from_item(item));
+                for s in Status::ALL {
+                    if i.key_pressed(s.key()) {
+                        status = Some(s);
+                    }
                 }
+
+                status
+            });
+
+            if let Some(status) = status
+                && status != self.status
+            {
+                canvas.edits.push(self.set_status(path, status));
             }
+        }
 
-            ui.min_rect()
-        };
-        ui.allocate_rect(child_rect, Sense::hover());
+        self.status.paint_overlay(ui.painter(), image_res.rect);
 
-        if self.next.is_some() || is_dragging_item {
-            ui.painter().line_segment(
-                [image_res.rect.center_bottom(), pos2(main_line_x, ui.cursor().top())],
-                ui.visuals().widgets.active.fg_stroke,
-            );
-        } else if let Some(pos) = last_line_pos {
-            ui.painter().line_segment(
-                [image_res.rect.center_bottom(), pos],
-                ui.visuals().widgets.inactive.fg_stroke,
+        if self.amount > 1 {
+            ui.painter().text(
+                image_res.rect.right_bottom(),
+                Align2::RIGHT_BOTTOM,
+                self.amount.to_string(),
+                ui.style().text_styles[&TextStyle::Body].clone(),
+                ui.visuals().strong_text_color(),
             );
         }
 
-        if let Some(ref mut first_child) = self.next {
-            let res = first_child.ui(dragging, ui);
+        if let Dragging::Item(item) = canvas.dragging
+            && ui.ctx().dragged_id().is_none()
+            && image_res.contains_pointer()
+        {
+            canvas.edits.push(vec![Op::Insert {
+                at: path.child(self.children.len()),
+                node: Node::from_item(item),
+            }]);
+        }
+
+        image_res.interact(Sense::click())
+    }
 
-            if res.clicked_by(egui::PointerButton::Middle) {
-                self.next = None;
+    /// Context menu entries for actions on this node itself. Callers add entries that need
+    /// access to the parent.
+    pub fn context_menu(&self, path: &Path, ui: &mut egui::Ui, edits: &mut Vec<Edit>) {
+        for status in Status::ALL {
+            let button = egui::Button::selectable(self.status == status, status.label())
+                .shortcut_text(ui.ctx().format_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::NONE,
+                    status.key(),
+                )));
+
+            if ui.add(button).clicked() && status != self.status {
+                edits.push(self.set_status(path, status));
             }
-        } else if is_dragging_item {
-            let (_res, item) = item_drag_target(ui, dragging);
+        }
+
+        if !self.children.is_empty()
+            && let Some(progress) = self.progress()
+        {
+            ui.weak(format!("{:.0}% of this branch obtained", progress * 100.));
+        }
+
+        ui.separator();
+
+        let recipes = pack::get().producing(&self.item.name).collect::<Vec<_>>();
 
-            if let Some(item) = item {
-                self.next = Some(Box::new(Node::from_item(item)));
+        match recipes.as_slice() {
+            [] => {
+                ui.add_enabled(false, egui::Button::new("Expand ingredients"))
+                    .on_disabled_hover_text("No known recipe");
+            }
+            [recipe] => {
+                if ui.button("Expand ingredients").clicked() {
+                    edits.push(self.expand(path, recipe, pack::get()));
+                }
+            }
+            recipes => {
+                ui.menu_button("Expand ingredients", |ui| {
+                    for recipe in recipes {
+                        if ui.button(expand::recipe_summary(recipe)).clicked() {
+                            edits.push(self.expand(path, recipe, pack::get()));
+                        }
+                    }
+                });
             }
         }
+    }
 
-        image_res.interact(Sense::click())
+    fn set_status(&self, path: &Path, status: Status) -> Edit {
+        vec![Op::SetStatus {
+            at: path.clone(),
+            old: self.status,
+            new: status,
+        }]
+    }
+
+    /// The share of this node and everything branching off it that has been obtained, not
+    /// counting the nodes after it. Skipped nodes and everything branching off them don't count
+    /// either way.
+    pub fn progress(&self) -> Option<f32> {
+        fraction(self.counts())
+    }
+
+    /// How many of this node and what branches off it are obtained, and how many count at all.
+    fn counts(&self) -> (u32, u32) {
+        branch_counts(self.status, self.children.iter().map(Node::chain_counts))
+    }
+
+    /// The counts of this node and of the nodes after it, added up.
+    fn chain_counts(&self) -> (u32, u32) {
+        std::iter::successors(Some(self), |node| node.next.as_deref())
+            .map(Node::counts)
+            .fold((0, 0), |(obtained, total), counts| (obtained + counts.0, total + counts.1))
     }
 }
 
-pub fn item_drag_target(
-    ui: &mut egui::Ui,
-    dragging: &mut Dragging,
-) -> (egui::Response, Option<&'static Item>) {
-    let (res, painter) = ui.allocate_painter(vec2(48., 48.), Sense::hover());
-
-    if let Dragging::Item(item) = dragging {
-        let rect = res.rect.shrink(4.);
-        let style = if res.contains_pointer() {
-            ui.visuals().widgets.active
-        } else {
-            ui.visuals().widgets.inactive
-        };
+/// Stands in for the icon of an item whose image wasn't found on the wiki: a frame with the
+/// item's name in it.
+fn paint_missing_image(ui: &egui::Ui, rect: Rect, name: &str) {
+    let rect = rect.shrink(2.);
+    let visuals = ui.visuals();
+
+    ui.painter().rect_stroke(
+        rect,
+        4.,
+        visuals.widgets.noninteractive.bg_stroke,
+        egui::StrokeKind::Inside,
+    );
 
-        painter.rect(rect, style.corner_radius, style.bg_fill, style.bg_stroke, StrokeKind::Inside);
+    let galley = ui.painter().layout(
+        name.to_owned(),
+        egui::FontId::proportional(9.),
+        visuals.weak_text_color(),
+        rect.width() - 4.,
+    );
+    ui.painter().with_clip_rect(rect).galley(
+        rect.center() - galley.size() / 2.,
+        galley,
+        visuals.weak_text_color(),
+    );
+}
 
-        painter.text(
-            rect.center(),
-            Align2::CENTER_CENTER,
-            "+",
-            ui.style().text_styles[&egui::TextStyle::Heading].clone(),
-            style.fg_stroke.color,
-        );
+/// The obtained and total nodes of a node with `status` and what branches off it, from the
+/// [`Node::chain_counts`] of its children. Nothing below a skipped node counts.
+fn branch_counts(status: Status, children: impl IntoIterator<Item = (u32, u32)>) -> (u32, u32) {
+    let own = match status {
+        Status::Obtained => (1, 1),
+        Status::Skipped => return (0, 0),
+        Status::Planned | Status::InProgress => (0, 1),
+    };
+
+    children
+        .into_iter()
+        .fold(own, |(obtained, total), counts| (obtained + counts.0, total + counts.1))
+}
 
-        if ui.ctx().dragged_id().is_none() && res.contains_pointer() {
-            return (res, Some(item));
-        }
+/// The share of obtained nodes, if there are any nodes to count.
+fn fraction((obtained, total): (u32, u32)) -> Option<f32> {
+    (total > 0).then(|| obtained as f32 / total as f32)
+}
+
+/// File menu entries for saving and opening tree files.
+pub fn file_menu(ui: &mut egui::Ui, app: &mut TerratreeApp) {
+    file::menu(ui, app);
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(trees) = &app.trees
+        && ui.button("Copy share link").clicked()
+    {
+        share::copy_link(ui.ctx(), trees.active());
     }
+}
 
-    (res, None)
+/// The Edit menu, for undoing and redoing changes to the shown tree.
+pub fn edit_menu(ui: &mut egui::Ui, app: &mut TerratreeApp) {
+    match &mut app.trees {
+        Some(trees) => history::menu(ui, trees.active_mut()),
+        None => {
+            ui.add_enabled(false, egui::Button::new("Undo"));
+            ui.add_enabled(false, egui::Button::new("Redo"));
+        }
+    }
 }
 
 pub fn ui(ctx: &egui::Context, app: &mut TerratreeApp) {
+    if app.shared.is_none()
+        && let Some(trees) = &mut app.trees
+    {
+        history::shortcuts(ctx, trees.active_mut());
+    }
+
+    file::ui(ctx, app);
+    shopping_list::ui(ctx, app);
+
     egui::SidePanel::right("items").show(ctx, |ui| {
         ui.heading("Items");
 
@@ -188,36 +386,105 @@ pub fn ui(ctx: &egui::Context, app: &mut TerratreeApp) {
             .hint_text("Search")
             .show(ui);
 
+        let journey_mode = app.ui_state.journey_mode;
+
+        if journey_mode {
+            let (researched, total) = app.journey.progress();
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut app.ui_state.only_unresearched, "Unresearched only");
+                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.weak(format!("{researched} / {total} researched"));
+                });
+            });
+        }
+
         ui.add_space(2.);
 
+        ui.horizontal(|ui| {
+            filter_combo(
+                ui,
+                "tag_filter",
+                "Any tag",
+                &mut app.ui_state.tag_filter,
+                tdata::tags().iter().map(String::as_str),
+            );
+            filter_combo(
+                ui,
+                "milestone_filter",
+                "Any milestone",
+                &mut app.ui_state.milestone_filter,
+                tdata::milestones().iter().map(|m| m.name.as_str()),
+            );
+        });
+
+        let search = app.ui_state.item_search.to_lowercase();
+        let tag_filter = app.ui_state.tag_filter.as_deref();
+        let milestone_filter = app.ui_state.milestone_filter.as_deref();
+        let only_unresearched = journey_mode && app.ui_state.only_unresearched;
+
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.with_layout(
                 egui::Layout::top_down(egui::Align::LEFT).with_cross_justify(true),
                 |ui| {
-                    for item in wiki_data::ITEMS.iter() {
-                        if !item
-                            .name
-                            .to_lowercase()
-                            .contains(&app.ui_state.item_search.to_lowercase())
-                        {
+                    let show_source = pack::get().len() > 1;
+
+                    for (source, item) in pack::get().items() {
+                        if !search.is_empty() && !item.name.to_lowercase().contains(&search) {
+                            continue;
+                        }
+
+                        if tag_filter.is_some() || milestone_filter.is_some() {
+                            let Some(entry) = tdata::item(&item.name) else {
+                                continue;
+                            };
+
+                            if tag_filter.is_some_and(|tag| !entry.tags.iter().any(|t| t == tag))
+                                || milestone_filter
+                                    .is_some_and(|m| entry.milestone.as_deref() != Some(m))
+                            {
+                                continue;
+                            }
+                        }
+
+                        if only_unresearched && app.journey.remaining(item).is_none_or(|r| r == 0) {
                             continue;
                         }
 
-                        let res = draw_sidebar_item(ui, item);
+                        let res = draw_sidebar_item(
+                            ui,
+                            item,
+                            show_source.then_some(source),
+                            journey_mode.then_some(&app.journey),
+                        )
+                        .on_hover_ui(|ui| widgets::tooltip::ui(ui, item));
 
                         if res.drag_started() {
                             ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Grab);
                             app.dragging = Dragging::Item(item);
                         }
 
+                        if res.double_clicked()
+                            && let Some(trees) = &mut app.trees
+                        {
+                            trees.active_mut().add_item(item);
+                        }
+
                         res.context_menu(|ui| {
+                            if ui.button("Add to tree").clicked()
+                                && let Some(trees) = &mut app.trees
+                            {
+                                trees.active_mut().add_item(item);
+                            }
+
                             if ui.button("Open Wiki").clicked() {
-                                let url = format!(
-                                    "https://terraria.wiki.gg/wiki/{}",
-                                    item.name.replace(' ', "_")
-                                );
+                                let url = source.wiki_page(&item.name);
                                 ui.ctx().open_url(OpenUrl::new_tab(url));
                             }
+
+                            if journey_mode {
+                                ui.separator();
+                                app.journey.context_menu(ui, item);
+                            }
                         });
                     }
                 },
@@ -226,79 +493,54 @@ pub fn ui(ctx: &egui::Context, app: &mut TerratreeApp) {
     });
 
     egui::CentralPanel::default().show(ctx, |ui| {
-        app.tree_root.ui(&mut app.dragging, ui);
-
-        // app.milestones.retain_mut(|milestone| {
-        //     let mut delete = false;
-
-        //     let res = ui
-        //         .horizontal(|ui| {
-        //             egui::Image::new(&milestone.image)
-        //                 .fit_to_exact_size(vec2(32., 32.))
-        //                 .ui(ui);
-
-        //             ui.heading(&milestone.title);
-
-        //             ui.take_available_width();
-        //         })
-        //         .response
-        //         .interact(Sense::click());
-
-        //     res.context_menu(|ui| {
-        //         let res = ui.text_edit_singleline(&mut milestone.title);
-        //         if ui.memory(|m| m.focused().is_none()) {
-        //             res.request_focus();
-        //         }
-        //         if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-        //             ui.close();
-        //         }
-
-        //         if ui.button("Delete").clicked() {
-        //             delete = true;
-        //         }
-        //     });
-
-        //     !delete
-        // });
-
-        // // new milestone drop target
-        // {
-        //     let (res, painter) =
-        //         ui.allocate_painter(vec2(ui.available_width(), 48.), Sense::hover());
-
-        //     if let Dragging::Item(item) = app.dragging {
-        //         let rect = res.rect.shrink(4.);
-        //         let style = if res.contains_pointer() {
-        //             ui.visuals().widgets.active
-        //         } else {
-        //             ui.visuals().widgets.inactive
-        //         };
-
-        //         painter.rect(
-        //             rect,
-        //             style.corner_radius,
-        //             style.bg_fill,
-        //             style.bg_stroke,
-        //             StrokeKind::Inside,
-        //         );
-
-        //         painter.text(
-        //             rect.center(),
-        //             Align2::CENTER_CENTER,
-        //             "Create milestone",
-        //             ui.style().text_styles[&egui::TextStyle::Heading].clone(),
-        //             style.fg_stroke.color,
-        //         );
-
-        //         if ctx.dragged_id().is_none() && res.contains_pointer() {
-        //             app.milestones.push(Node { item, children: Vec::new() });
-        //         }
-        //     }
-        // }
+        if share::ui(ui, app) {
+            return;
+        }
+
+        let Some(trees) = &mut app.trees else {
+            return;
+        };
+
+        trees::tab_bar(ui, trees);
+        ui.separator();
+
+        let tree = trees.active_mut();
+        let mut edits = Vec::new();
+
+        canvas::ui(ui, &tree.name, &mut tree.milestones, &app.dragging, &mut edits);
+
+        for edit in edits {
+            tree.apply(edit);
+        }
     });
 }
 
-fn draw_sidebar_item(ui: &mut egui::Ui, item: &wiki_data::item::Item) -> egui::Response {
+/// A combo box to pick one of `options` to filter by, or none.
+fn filter_combo<'a>(
+    ui: &mut egui::Ui,
+    id: &str,
+    any: &str,
+    filter: &mut Option<String>,
+    options: impl Iterator<Item = &'a str>,
+) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(filter.as_deref().unwrap_or(any))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(filter, None, any);
+            for option in options {
+                ui.selectable_value(filter, Some(option.to_owned()), option);
+            }
+        });
+}
+
+/// Draws an item in the sidebar list. `source` is the pack the item comes from, if it should be
+/// shown, and `journey` the research progress, in Journey mode.
+fn draw_sidebar_item(
+    ui: &mut egui::Ui,
+    item: &'static ArchivedItem,
+    source: Option<&DataPack>,
+    journey: Option<&Journey>,
+) -> egui::Response {
     let size = 32.;
     let margin = ui.spacing().item_spacing.y;
     let (res, painter) =
@@ -312,23 +554,51 @@ fn draw_sidebar_item(ui: &mut egui::Ui, item: &wiki_data::item::Item) -> egui::R
 
     let (img_rect, info_rect) = rect.split_left_right_at_x(rect.left() + rect.height());
 
-    if let Some(image) = item.image_location.as_ref() {
-        ui.place(img_rect, egui::Image::new(format!("wiki://{}", image.name)));
+    if let Some(image) = pack::get().image_name(item) {
+        ui.place(img_rect, egui::Image::new(format!("wiki://{image}")));
     }
 
     let info_rect = info_rect.shrink2(vec2(8., 3.));
 
-    let color = rarity_color(ui, item.rarity);
+    let color = widgets::rarity::color(ui, item.rarity());
 
     painter.text(
         info_rect.left_top(),
         Align2::LEFT_TOP,
-        &item.name,
+        item.name.as_str(),
         ui.style().text_styles[&egui::TextStyle::Body].clone(),
         color,
     );
 
+    let price = item
+        .buy
+        .as_ref()
+        .map(|buy| (Coins::from(buy), "Buy price"))
+        .or_else(|| {
+            item.sell
+                .as_ref()
+                .map(|sell| (Coins::from(sell), "Sell price"))
+        });
+
+    if let Some((price, label)) = price {
+        ui.scope_builder(
+            UiBuilder::new()
+                .max_rect(info_rect)
+                .layout(Layout::right_to_left(egui::Align::Min)),
+            |ui| widgets::coins::ui(ui, price).on_hover_text(format!("{label}: {price}")),
+        );
+    }
+
+    let research = journey
+        .and_then(|journey| journey.remaining(item))
+        .map(|remaining| match remaining {
+            0 => "Researched".to_owned(),
+            remaining => format!("{remaining} left to research"),
+        });
+
     let subtext = [
+        research.unwrap_or_default(),
+        source.map(|s| s.name.clone()).unwrap_or_default(),
         item.tooltip
             .as_ref()
             .and_then(|t| t.plain().split('\n').next().map(|s| s.to_string()))
@@ -345,6 +615,11 @@ fn draw_sidebar_item(ui: &mut egui::Ui, item: &wiki_data::item::Item) -> egui::R
                 format!("{d} {ty}damage")
             })
             .unwrap_or_default(),
+        item.powers()
+            .into_iter()
+            .map(|(power, kind)| format!("{power}% {kind} power"))
+            .collect::<Vec<_>>()
+            .join(", "),
         item.types
             .iter()
             .map(|t| t.to_string())
@@ -369,16 +644,41 @@ fn draw_sidebar_item(ui: &mut egui::Ui, item: &wiki_data::item::Item) -> egui::R
     res
 }
 
-fn rarity_color(ui: &egui::Ui, rarity: Rarity) -> Color32 {
-    match rarity.color() {
-        RarityColor::Static { r, g, b } => Color32::from_rgb(r, g, b),
-        RarityColor::Expert => {
-            ui.ctx().request_repaint();
-            Hsva::new(((ui.input(|i| i.time) / 2.) % 1.) as f32, 1., 1., 1.).into()
-        }
-        RarityColor::Master => {
-            ui.ctx().request_repaint();
-            Hsva::new((((ui.input(|i| i.time) / 2.) % 0.3) as f32 - 0.15).abs(), 1., 1., 1.).into()
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, status: Status, children: Vec<Node>) -> Node {
+        let mut node = Node::from_name(name).unwrap();
+        node.status = status;
+        node.children = children;
+        node
+    }
+
+    #[test]
+    fn progress() {
+        pack::load_embedded();
+
+        let leaf = node("Wood", Status::Planned, vec![]);
+        assert_eq!(leaf.progress(), Some(0.));
+        assert_eq!(node("Wood", Status::Skipped, vec![]).progress(), None);
+
+        // The node after a child counts towards the parent too, but not the one after the node
+        // itself.
+        let mut bar = node("Copper Bar", Status::Obtained, vec![]);
+        bar.next = Some(Box::new(node("Wood", Status::Obtained, vec![])));
+        let mut pickaxe = node("Copper Pickaxe", Status::InProgress, vec![bar]);
+        pickaxe.next = Some(Box::new(node("Gel", Status::Obtained, vec![])));
+        assert_eq!(pickaxe.progress(), Some(2. / 3.));
+
+        // Nothing below a skipped node counts, even if it was obtained.
+        let skipped =
+            node("Copper Bar", Status::Skipped, vec![node("Copper Ore", Status::Obtained, vec![])]);
+        let pickaxe = node(
+            "Copper Pickaxe",
+            Status::Obtained,
+            vec![skipped, node("Wood", Status::Planned, vec![])],
+        );
+        assert_eq!(pickaxe.progress(), Some(1. / 2.));
     }
 }
@@ -0,0 +1,600 @@
+//! Share links: a tree encoded into the URL fragment of the web build, so it can be shared by
+//! copying the address bar. Items are stored by pack namespace and item id, the whole thing
+//! deflated and written as URL-safe base64.
+
+// Links are only made on web, where there's an address bar to put them in.
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+
+use std::io::{Read as _, Write as _};
+
+use anyhow::{Context as _, bail};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use flate2::{Compression, read::DeflateDecoder, write::DeflateEncoder};
+
+use wiki_data::DataPack;
+
+use super::canvas;
+use crate::{
+    TerratreeApp,
+    app::Dragging,
+    pack,
+    tree::{Node, Status, Tree, milestone::Milestone},
+};
+
+/// The version of the encoding, written as the first byte. Version 1 links referred to packs by
+/// their position in the loaded packs, and are still read that way.
+const VERSION: u8 = 2;
+
+/// The fragment a share link starts with, before the encoded tree.
+pub const FRAGMENT_PREFIX: &str = "tree=";
+
+/// The most a link may inflate to, so a small link can't make the app allocate without bound.
+const MAX_SIZE: u64 = 1 << 20;
+
+/// How deep nodes may be nested, so a crafted link can't overflow the stack. Nodes are dropped
+/// and drawn recursively through both their children and the node after them, so each of those
+/// counts as a level.
+const MAX_DEPTH: usize = 512;
+
+const HAS_NEXT: u8 = 1 << 2;
+const HAS_AMOUNT: u8 = 1 << 3;
+
+/// Encodes a tree for the URL fragment, without the [`FRAGMENT_PREFIX`]. Fails for trees nested
+/// deeper than links may be.
+pub fn encode(tree: &Tree) -> anyhow::Result<String> {
+    let mut bytes = vec![VERSION];
+    write_str(&mut bytes, &tree.name);
+
+    let packs = pack::get();
+    write_varint(&mut bytes, packs.len() as u64);
+    for pack in packs.iter() {
+        write_str(&mut bytes, &pack.namespace);
+    }
+
+    write_varint(&mut bytes, tree.milestones.len() as u64);
+
+    for milestone in &tree.milestones {
+        write_str(&mut bytes, &milestone.title);
+        write_str(&mut bytes, milestone.image.as_deref().unwrap_or_default());
+        write_varint(&mut bytes, milestone.roots.len() as u64);
+
+        for root in &milestone.roots {
+            write_node(&mut bytes, root, 0)?;
+        }
+    }
+
+    Ok(compress(&bytes))
+}
+
+fn compress(bytes: &[u8]) -> String {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(bytes)
+        .expect("writing to a Vec can't fail");
+    let compressed = encoder.finish().expect("writing to a Vec can't fail");
+
+    URL_SAFE_NO_PAD.encode(compressed)
+}
+
+/// Decodes a tree from a URL fragment, with or without the [`FRAGMENT_PREFIX`]. Returns it along
+/// with the number of nodes left out because their item isn't in the loaded data packs.
+pub fn decode(fragment: &str) -> anyhow::Result<(Tree, usize)> {
+    let fragment = fragment.trim_start_matches('#');
+    let encoded = fragment.strip_prefix(FRAGMENT_PREFIX).unwrap_or(fragment);
+
+    let compressed = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .context("not a share link")?;
+
+    let mut bytes = Vec::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_SIZE)
+        .read_to_end(&mut bytes)
+        .context("not a share link")?;
+
+    let mut reader = Reader {
+        bytes: &bytes,
+        packs: Vec::new(),
+        missing: 0,
+    };
+
+    let version = reader.byte()?;
+    if version > VERSION {
+        bail!("share link version {version} is not supported");
+    }
+
+    let name = reader.string()?;
+
+    reader.packs = if version == 1 {
+        pack::get().iter().map(Some).collect()
+    } else {
+        (0..reader.varint()?)
+            .map(|_| {
+                let namespace = reader.string()?;
+                Ok(pack::get().iter().find(|pack| pack.namespace == namespace))
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+    let mut milestones = Vec::new();
+
+    for _ in 0..reader.varint()? {
+        let title = reader.string()?;
+        let image = Some(reader.string()?).filter(|image| !image.is_empty());
+
+        let mut roots = Vec::new();
+        for _ in 0..reader.varint()? {
+            roots.extend(reader.node(0)?);
+        }
+
+        milestones.push(Milestone { image, title, roots });
+    }
+
+    Ok((Tree::from_milestones(name, milestones), reader.missing))
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    write_varint(bytes, s.len() as u64);
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+/// Writes an item id, zigzag encoded so the negative ids of some items stay short.
+fn write_id(bytes: &mut Vec<u8>, id: i32) {
+    write_varint(bytes, ((id << 1) ^ (id >> 31)) as u32 as u64);
+}
+
+/// Writes a node and the nodes after it, each as the index of its pack in the pack list, its
+/// item id, a byte of flags holding the status, then its amount if it isn't one and its
+/// children. `depth` is how deep the node is nested, see [`MAX_DEPTH`].
+fn write_node(bytes: &mut Vec<u8>, node: &Node, depth: usize) -> anyhow::Result<()> {
+    let packs = pack::get();
+    let mut node = node;
+
+    for depth in depth..=MAX_DEPTH {
+        let pack = packs
+            .source(node.item)
+            .and_then(|source| packs.iter().position(|pack| std::ptr::eq(pack, source)))
+            .unwrap_or_default();
+
+        write_varint(bytes, pack as u64);
+        write_id(bytes, node.item.item_id.to_native());
+
+        let status = Status::ALL
+            .iter()
+            .position(|s| *s == node.status)
+            .unwrap_or_default() as u8;
+        let mut flags = status;
+        if node.next.is_some() {
+            flags |= HAS_NEXT;
+        }
+        if node.amount != 1 {
+            flags |= HAS_AMOUNT;
+        }
+        bytes.push(flags);
+
+        if node.amount != 1 {
+            write_varint(bytes, node.amount as u64);
+        }
+
+        write_varint(bytes, node.children.len() as u64);
+        for child in &node.children {
+            write_node(bytes, child, depth + 1)?;
+        }
+
+        match node.next.as_deref() {
+            Some(next) => node = next,
+            None => return Ok(()),
+        }
+    }
+
+    bail!("the tree nests nodes too deeply to share")
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    /// The packs the link refers to, by index. `None` for packs that aren't loaded.
+    packs: Vec<Option<&'static DataPack>>,
+    /// How many nodes were left out so far because their item wasn't found.
+    missing: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        let (&byte, rest) = self.bytes.split_first().context("share link is cut off")?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> anyhow::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("share link has a number that is too large")
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let len = usize::try_from(self.varint()?)?;
+        if len > self.bytes.len() {
+            bail!("share link is cut off");
+        }
+
+        let (s, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(String::from_utf8(s.to_vec())?)
+    }
+
+    /// Reads a node and the nodes after it, or `None` if none of their items are in the loaded
+    /// packs. Nodes whose item isn't are left out along with their children, and the node after
+    /// them takes their place. `depth` is how deep the node is nested, see [`MAX_DEPTH`].
+    fn node(&mut self, depth: usize) -> anyhow::Result<Option<Node>> {
+        let mut chain = Vec::new();
+
+        for depth in depth..=MAX_DEPTH {
+            let pack = usize::try_from(self.varint()?)?;
+            let id = self.varint()? as u32;
+            let id = ((id >> 1) as i32) ^ -((id & 1) as i32);
+
+            let flags = self.byte()?;
+            let status = Status::ALL
+                .get(usize::from(flags & 0b11))
+                .copied()
+                .unwrap_or_default();
+            let amount = if flags & HAS_AMOUNT != 0 {
+                u32::try_from(self.varint()?)?
+            } else {
+                1
+            };
+
+            let mut children = Vec::new();
+            for _ in 0..self.varint()? {
+                children.extend(self.node(depth + 1)?);
+            }
+
+            match self
+                .packs
+                .get(pack)
+                .copied()
+                .flatten()
+                .and_then(|pack| pack.items.by_id(id))
+            {
+                Some(item) => chain.push(Node {
+                    item,
+                    amount,
+                    status,
+                    next: None,
+                    children,
+                }),
+                None => self.missing += 1,
+            }
+
+            if flags & HAS_NEXT == 0 {
+                return Ok(chain.into_iter().rev().fold(None, |next, mut node| {
+                    node.next = next.map(Box::new);
+                    Some(node)
+                }));
+            }
+        }
+
+        bail!("share link nests nodes too deeply")
+    }
+}
+
+/// A tree opened from a share link, shown read-only until it's copied to the user's trees.
+pub struct SharedTree {
+    tree: Tree,
+    /// How many nodes were left out because their item isn't in the loaded data packs.
+    missing: usize,
+}
+
+impl SharedTree {
+    /// The share link in the URL fragment the app was opened with, if any.
+    pub fn link(cc: &eframe::CreationContext<'_>) -> Option<String> {
+        #[cfg(target_arch = "wasm32")]
+        let hash = Some(cc.integration_info.web_info.location.hash.clone());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let hash = {
+            let _ = cc;
+            None::<String>
+        };
+
+        hash.filter(|hash| hash.trim_start_matches('#').starts_with(FRAGMENT_PREFIX))
+    }
+
+    /// Reads the tree from a share link. Needs the data packs to be loaded.
+    pub fn open(link: &str) -> Option<Self> {
+        match decode(link) {
+            Ok((tree, missing)) => Some(Self { tree, missing }),
+            Err(e) => {
+                log::warn!("failed to open share link: {e:#}");
+                None
+            }
+        }
+    }
+}
+
+/// Puts a share link for `tree` in the address bar and copies it.
+#[cfg(target_arch = "wasm32")]
+pub fn copy_link(ctx: &egui::Context, tree: &Tree) {
+    let Some(location) = web_sys::window().map(|window| window.location()) else {
+        return;
+    };
+
+    let encoded = match encode(tree) {
+        Ok(encoded) => encoded,
+        Err(e) => {
+            log::warn!("failed to make a share link: {e:#}");
+            return;
+        }
+    };
+
+    if let Err(e) = location.set_hash(&format!("{FRAGMENT_PREFIX}{encoded}")) {
+        log::warn!("failed to set the share link: {e:?}");
+        return;
+    }
+
+    if let Ok(href) = location.href() {
+        ctx.copy_text(href);
+    }
+}
+
+/// Removes the share link from the address bar, once the shared tree is closed.
+fn clear_link() {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(window) = web_sys::window() {
+        window.location().set_hash("").ok();
+    }
+}
+
+/// Draws the shared tree, if there is one, with a bar to copy it to the user's trees or close
+/// it. Returns whether there was one to draw.
+pub fn ui(ui: &mut egui::Ui, app: &mut TerratreeApp) -> bool {
+    let Some(shared) = &app.shared else {
+        return false;
+    };
+
+    let mut close = false;
+
+    ui.horizontal(|ui| {
+        ui.strong(format!("Shared tree: {}", shared.tree.name));
+        ui.weak("(read-only)");
+
+        if ui.button("Copy to my trees").clicked()
+            && let Some(trees) = &mut app.trees
+        {
+            trees.add(shared.tree.clone());
+            close = true;
+        }
+
+        if ui.button("Close").clicked() {
+            close = true;
+        }
+    });
+
+    if shared.missing > 0 {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!(
+                "{} items of this tree aren't in the loaded data, so they were left out",
+                shared.missing
+            ),
+        );
+    }
+
+    ui.separator();
+
+    // Drawn from a copy with nothing dragged, and with edits thrown away, so it can't change.
+    let mut milestones = shared.tree.milestones.clone();
+    canvas::ui(ui, "shared", &mut milestones, &Dragging::None, &mut Vec::new());
+
+    if close {
+        app.shared = None;
+        clear_link();
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        pack::load_embedded();
+
+        let mut root = Node::from_name("Copper Pickaxe").unwrap();
+        root.status = Status::Obtained;
+
+        let mut wood = Node::from_name("Wood").unwrap();
+        wood.amount = 12;
+        wood.next = Node::from_name("Work Bench").map(Box::new);
+        root.children.push(wood);
+
+        let tree = Tree::from_milestones(
+            "Shared".to_owned(),
+            vec![Milestone {
+                image: None,
+                title: "Start".to_owned(),
+                roots: vec![root],
+            }],
+        );
+
+        let link = encode(&tree).unwrap();
+        assert!(
+            link.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+
+        let (decoded, missing) = decode(&format!("#{FRAGMENT_PREFIX}{link}")).unwrap();
+        assert_eq!(missing, 0);
+        assert_eq!(decoded.name, "Shared");
+        assert_eq!(encode(&decoded).unwrap(), link);
+
+        let root = &decoded.milestones[0].roots[0];
+        assert_eq!(root.status, Status::Obtained);
+        assert_eq!(root.children[0].amount, 12);
+        assert_eq!(root.children[0].next.as_ref().unwrap().item.name, "Work Bench");
+    }
+
+    #[test]
+    fn missing_items() {
+        pack::load_embedded();
+
+        let id = |name| {
+            let item = pack::get().item_by_name(name).unwrap();
+            item.item_id.to_native()
+        };
+
+        let mut bytes = vec![VERSION];
+        write_str(&mut bytes, "Shared");
+        write_varint(&mut bytes, 2);
+        write_str(&mut bytes, "calamity");
+        write_str(&mut bytes, "terraria");
+        write_varint(&mut bytes, 1);
+        write_str(&mut bytes, "Start");
+        write_str(&mut bytes, "");
+        write_varint(&mut bytes, 2);
+
+        // Copper Pickaxe, with a child from a pack that isn't loaded followed by Wood.
+        write_varint(&mut bytes, 1);
+        write_id(&mut bytes, id("Copper Pickaxe"));
+        bytes.push(0);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, 0);
+        write_id(&mut bytes, id("Copper Pickaxe"));
+        bytes.push(HAS_NEXT);
+        write_varint(&mut bytes, 0);
+        write_varint(&mut bytes, 1);
+        write_id(&mut bytes, id("Wood"));
+        bytes.push(0);
+        write_varint(&mut bytes, 0);
+
+        // An item the loaded pack doesn't have, and a pack index past the list.
+        write_varint(&mut bytes, 1);
+        write_id(&mut bytes, i32::MAX);
+        bytes.push(HAS_NEXT);
+        write_varint(&mut bytes, 0);
+        write_varint(&mut bytes, 5);
+        write_id(&mut bytes, id("Wood"));
+        bytes.push(0);
+        write_varint(&mut bytes, 0);
+
+        let (tree, missing) = decode(&compress(&bytes)).unwrap();
+        assert_eq!(missing, 3);
+
+        let roots = &tree.milestones[0].roots;
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].item.name, "Copper Pickaxe");
+        assert_eq!(roots[0].children.len(), 1);
+        assert_eq!(roots[0].children[0].item.name, "Wood");
+    }
+
+    /// A tree with a single root, nested `depth` levels deep by `nest`.
+    fn deep_tree(depth: usize, mut nest: impl FnMut(Node, Node) -> Node) -> Tree {
+        let mut root = Node::from_name("Wood").unwrap();
+        for _ in 0..depth {
+            root = nest(Node::from_name("Gel").unwrap(), root);
+        }
+
+        Tree::from_milestones(
+            "Deep".to_owned(),
+            vec![Milestone {
+                image: None,
+                title: "Start".to_owned(),
+                roots: vec![root],
+            }],
+        )
+    }
+
+    fn then(node: Node, next: Node) -> Node {
+        Node { next: Some(Box::new(next)), ..node }
+    }
+
+    fn parent(node: Node, child: Node) -> Node {
+        Node { children: vec![child], ..node }
+    }
+
+    #[test]
+    fn depth_limit() {
+        pack::load_embedded();
+
+        let tree = deep_tree(MAX_DEPTH, then);
+        let (decoded, _) = decode(&encode(&tree).unwrap()).unwrap();
+        let chain =
+            std::iter::successors(decoded.milestones[0].roots.first(), |node| node.next.as_deref());
+        assert_eq!(chain.count(), MAX_DEPTH + 1);
+
+        assert!(encode(&deep_tree(MAX_DEPTH, parent)).is_ok());
+        assert!(encode(&deep_tree(MAX_DEPTH + 1, then)).is_err());
+        assert!(encode(&deep_tree(MAX_DEPTH + 1, parent)).is_err());
+
+        // Alternating between the two counts every level against the same limit.
+        let mut alternate = false;
+        let mixed = |node, inner| {
+            alternate = !alternate;
+            if alternate {
+                then(node, inner)
+            } else {
+                parent(node, inner)
+            }
+        };
+        assert!(encode(&deep_tree(MAX_DEPTH + 1, mixed)).is_err());
+    }
+
+    #[test]
+    fn deep_links() {
+        pack::load_embedded();
+
+        let gel = pack::get().item_by_name("Gel").unwrap().item_id.to_native();
+
+        // A root followed by `chain` nodes, the last of which is nested `nested` levels deep.
+        let link = |chain: usize, nested: usize| {
+            let mut bytes = vec![VERSION];
+            write_str(&mut bytes, "Deep");
+            write_varint(&mut bytes, 1);
+            write_str(&mut bytes, "terraria");
+            write_varint(&mut bytes, 1);
+            write_str(&mut bytes, "Start");
+            write_str(&mut bytes, "");
+            write_varint(&mut bytes, 1);
+
+            for i in 0..=chain {
+                write_varint(&mut bytes, 0);
+                write_id(&mut bytes, gel);
+                bytes.push(if i < chain { HAS_NEXT } else { 0 });
+                write_varint(&mut bytes, u64::from(i == chain && nested > 0));
+            }
+            for i in 0..nested {
+                write_varint(&mut bytes, 0);
+                write_id(&mut bytes, gel);
+                bytes.push(0);
+                write_varint(&mut bytes, u64::from(i + 1 < nested));
+            }
+
+            compress(&bytes)
+        };
+
+        assert!(decode(&link(MAX_DEPTH / 2, MAX_DEPTH / 2)).is_ok());
+        assert!(decode(&link(MAX_DEPTH / 2, MAX_DEPTH / 2 + 1)).is_err());
+        assert!(decode(&link(MAX_DEPTH + 1, 0)).is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode("tree=not base64!").is_err());
+        assert!(decode("tree=AAAA").is_err());
+    }
+}
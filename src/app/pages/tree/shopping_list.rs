@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use egui::{Image, Sense, Widget as _, vec2};
+use wiki_data::{ArchivedItem, item::Coins, recipe::ArchivedStation};
+
+use crate::{
+    TerratreeApp,
+    app::widgets,
+    pack::{self, RecipeSource},
+    tree::{Node, Revision, Tree},
+};
+
+/// Raw materials and crafting stations needed to complete a tree.
+#[derive(Default)]
+pub struct ShoppingList {
+    pub materials: BTreeMap<&'static str, (&'static ArchivedItem, u32)>,
+    pub stations: Vec<&'static ArchivedStation>,
+}
+
+/// The shopping list of the shown tree, kept until the tree changes so it isn't totalled again
+/// every frame.
+#[derive(Default)]
+pub struct CachedShoppingList {
+    revision: Option<Revision>,
+    list: ShoppingList,
+}
+
+impl CachedShoppingList {
+    /// The shopping list of `tree`, totalled again only if it's a different tree or revision
+    /// than last time.
+    pub fn get(&mut self, tree: &Tree) -> &ShoppingList {
+        let revision = tree.history.revision();
+        if self.revision != Some(revision) {
+            self.list = ShoppingList::from_tree(tree, pack::get());
+            self.revision = Some(revision);
+        }
+
+        &self.list
+    }
+}
+
+impl ShoppingList {
+    /// Totals the leaves of the roots of a tree and their `next` chains. Obtained and skipped
+    /// nodes are left out along with everything below them, since their ingredients are no
+    /// longer needed. Stations come from the recipes in `recipes`.
+    pub fn from_tree(tree: &Tree, recipes: &impl RecipeSource) -> Self {
+        let mut list = Self::default();
+        for root in tree.roots() {
+            list.add(root, recipes);
+        }
+        list.stations.sort_by_key(|s| s.to_string());
+        list
+    }
+
+    fn add(&mut self, node: &Node, recipes: &impl RecipeSource) {
+        if !node.status.is_done() {
+            if node.children.is_empty() {
+                self.materials
+                    .entry(node.item.name.as_str())
+                    .or_insert((node.item, 0))
+                    .1 += node.amount;
+            } else {
+                for child in &node.children {
+                    self.add(child, recipes);
+                }
+
+                if let Some(stations) = crafting_stations(node, recipes) {
+                    for station in stations {
+                        if !self.stations.contains(&station) {
+                            self.stations.push(station);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(next) = &node.next {
+            self.add(next, recipes);
+        }
+    }
+
+    /// What buying all materials from vendors would cost, along with the number of materials
+    /// that no vendor sells.
+    pub fn vendor_cost(&self) -> (Coins, usize) {
+        let mut unsold = 0;
+
+        let cost = self
+            .materials
+            .values()
+            .filter_map(|(item, amount)| {
+                let price = item.buy.as_ref().map(Coins::from);
+                if price.is_none() {
+                    unsold += 1;
+                }
+
+                Some(price? * u64::from(*amount))
+            })
+            .sum();
+
+        (cost, unsold)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+
+        for (name, (_, amount)) in &self.materials {
+            out += &format!("{amount} {name}\n");
+        }
+
+        let (cost, unsold) = self.vendor_cost();
+        if !cost.is_zero() {
+            out += &format!("\nVendor cost: {cost}");
+            if unsold > 0 {
+                out += &format!(" (excluding {unsold} materials not sold by vendors)");
+            }
+            out += "\n";
+        }
+
+        if !self.stations.is_empty() {
+            out += "\nStations:\n";
+            for station in &self.stations {
+                out += &format!("{station}\n");
+            }
+        }
+
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("kind,name,amount\n");
+
+        for (name, (_, amount)) in &self.materials {
+            out += &format!("material,{},{amount}\n", csv_field(name));
+        }
+
+        for station in &self.stations {
+            out += &format!("station,{},\n", csv_field(&station.to_string()));
+        }
+
+        out
+    }
+}
+
+/// The stations of the recipe that best matches the children of `node`, if any recipe uses any
+/// of them.
+fn crafting_stations(
+    node: &Node,
+    recipes: &impl RecipeSource,
+) -> Option<&'static [ArchivedStation]> {
+    recipes
+        .producing(&node.item.name)
+        .map(|recipe| {
+            let matches = node
+                .children
+                .iter()
+                .filter(|child| recipe.uses(&child.item.name))
+                .count();
+
+            (matches, recipe)
+        })
+        .max_by_key(|&(matches, _)| matches)
+        .filter(|&(matches, _)| matches > 0)
+        .map(|(_, recipe)| recipe.stations.as_slice())
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+pub fn ui(ctx: &egui::Context, app: &mut TerratreeApp) {
+    egui::SidePanel::left("shopping_list").show_animated(
+        ctx,
+        app.ui_state.show_shopping_list,
+        |ui| {
+            ui.heading("Shopping list");
+
+            let Some(trees) = &app.trees else {
+                return;
+            };
+
+            let list = app.shopping_list.get(trees.active());
+
+            ui.horizontal(|ui| {
+                if ui.button("Copy as text").clicked() {
+                    ui.ctx().copy_text(list.to_text());
+                }
+
+                if ui.button("Copy as CSV").clicked() {
+                    ui.ctx().copy_text(list.to_csv());
+                }
+            });
+
+            ui.add_space(2.);
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (name, (item, amount)) in &list.materials {
+                    ui.horizontal(|ui| {
+                        if let Some(image) = pack::get().image_name(item) {
+                            Image::new(format!("wiki://{image}"))
+                                .fit_to_exact_size(vec2(16., 16.))
+                                .ui(ui);
+                        } else {
+                            ui.allocate_exact_size(vec2(16., 16.), Sense::hover());
+                        }
+
+                        ui.label(format!("{amount} {name}"));
+                    });
+                }
+
+                let (cost, unsold) = list.vendor_cost();
+                if !cost.is_zero() {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.strong("Vendor cost");
+                        widgets::coins::ui(ui, cost);
+                    });
+
+                    if unsold > 0 {
+                        ui.weak(format!("Excluding {unsold} materials not sold by vendors"));
+                    }
+                }
+
+                if !list.stations.is_empty() {
+                    ui.separator();
+                    ui.strong("Stations");
+
+                    for station in &list.stations {
+                        ui.label(station.to_string());
+                    }
+                }
+            });
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::pages::tree::test_support::{graph, recipe},
+        tree::{Status, milestone::Milestone},
+    };
+
+    fn node(name: &str, amount: u32, children: Vec<Node>) -> Node {
+        let mut node = Node::from_name(name).unwrap();
+        node.amount = amount;
+        node.children = children;
+        node
+    }
+
+    fn list(roots: Vec<Node>) -> ShoppingList {
+        let recipes = graph(vec![
+            recipe("Copper Pickaxe", 1, &[("Copper Bar", 1), ("Wood", 1)], &["Iron Anvil"]),
+            recipe("Copper Bar", 1, &[("Copper Ore", 1)], &["Furnace"]),
+            recipe("Torch", 1, &[("Gel", 1), ("Wood", 1)], &[]),
+            recipe("Wooden Sword", 1, &[("Wood", 1)], &["Work Bench"]),
+        ]);
+
+        let tree = Tree::from_milestones(
+            "Plan".to_owned(),
+            vec![Milestone {
+                image: None,
+                title: "Start".to_owned(),
+                roots,
+            }],
+        );
+
+        ShoppingList::from_tree(&tree, &recipes)
+    }
+
+    #[test]
+    fn totals_leaves() {
+        pack::load_embedded();
+
+        let mut torch = node("Torch", 3, vec![node("Gel", 3, vec![]), node("Wood", 3, vec![])]);
+        torch.next = Some(Box::new(node("Wood", 2, vec![])));
+
+        let mut obtained = node("Copper Bar", 12, vec![node("Copper Ore", 36, vec![])]);
+        obtained.status = Status::Obtained;
+        let pickaxe = node("Copper Pickaxe", 1, vec![obtained, node("Wood", 4, vec![])]);
+
+        let list = list(vec![pickaxe, torch]);
+
+        let materials = list
+            .materials
+            .iter()
+            .map(|(&name, &(_, amount))| (name, amount))
+            .collect::<Vec<_>>();
+        assert_eq!(materials, [("Gel", 3), ("Wood", 9)]);
+
+        let stations = list
+            .stations
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(stations, ["Iron Anvil"]);
+    }
+
+    #[test]
+    fn stations_need_a_matching_recipe() {
+        pack::load_embedded();
+
+        // Neither recipe for these uses their children, so no station is listed for them.
+        let sword = node("Wooden Sword", 1, vec![node("Gel", 1, vec![])]);
+        let bar = node("Copper Bar", 1, vec![node("Iron Ore", 3, vec![])]);
+
+        assert!(list(vec![sword, bar]).stations.is_empty());
+    }
+
+    #[test]
+    fn text_and_csv() {
+        pack::load_embedded();
+
+        let bar = node("Copper Bar", 2, vec![node("Copper Ore", 6, vec![])]);
+        let list = list(vec![bar, node("Gel", 1, vec![])]);
+
+        let text = list.to_text();
+        assert!(text.starts_with("6 Copper Ore\n1 Gel\n"));
+        assert!(text.ends_with("\nStations:\nFurnace\n"));
+
+        assert_eq!(
+            list.to_csv(),
+            "kind,name,amount\nmaterial,Copper Ore,6\nmaterial,Gel,1\nstation,Furnace,\n",
+        );
+        assert_eq!(csv_field("Say \"hi\", twice"), "\"Say \"\"hi\"\", twice\"");
+    }
+}
@@ -0,0 +1,57 @@
+use egui::{Align2, Color32, FontId, Rect, Stroke, pos2, vec2};
+
+use crate::tree::Status;
+
+impl Status {
+    /// The key that sets this status on the hovered node.
+    pub fn key(self) -> egui::Key {
+        match self {
+            Self::Planned => egui::Key::Num1,
+            Self::InProgress => egui::Key::Num2,
+            Self::Obtained => egui::Key::Num3,
+            Self::Skipped => egui::Key::Num4,
+        }
+    }
+
+    /// Draws a badge for this status over the top left corner of an icon.
+    pub fn paint_overlay(self, painter: &egui::Painter, rect: Rect) {
+        let Some(color) = self.color() else {
+            return;
+        };
+
+        let symbol = match self {
+            Self::Planned => return,
+            Self::InProgress => "…",
+            Self::Obtained => "✔",
+            Self::Skipped => {
+                painter.line_segment(
+                    [rect.left_bottom(), rect.right_top()],
+                    Stroke::new(2., Color32::from_gray(140)),
+                );
+                "✖"
+            }
+        };
+
+        let center = rect.left_top() + vec2(8., 8.);
+        painter.circle_filled(center, 7., color);
+        painter.text(
+            center,
+            Align2::CENTER_CENTER,
+            symbol,
+            FontId::proportional(10.),
+            Color32::WHITE,
+        );
+    }
+}
+
+/// Draws how much of a subtree is done as a thin bar along the bottom of an icon.
+pub fn paint_progress(painter: &egui::Painter, rect: Rect, progress: f32) {
+    let bar = Rect::from_min_max(pos2(rect.left(), rect.bottom() - 3.), rect.right_bottom());
+
+    painter.rect_filled(bar, 1., Color32::from_black_alpha(160));
+    painter.rect_filled(
+        Rect::from_min_max(bar.min, pos2(bar.left() + bar.width() * progress, bar.bottom())),
+        1.,
+        Color32::from_rgb(0x40, 0xb0, 0x40),
+    );
+}
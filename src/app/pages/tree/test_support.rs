@@ -0,0 +1,35 @@
+//! Builders shared by the tests of the tree pages.
+
+use wiki_data::{
+    Recipe,
+    recipe::{Ingredient, RecipeGraph, Station},
+};
+
+use crate::pack;
+
+/// A recipe making `amount` of `result` from `ingredients` and their amounts, at `stations`.
+pub fn recipe(result: &str, amount: u32, ingredients: &[(&str, u32)], stations: &[&str]) -> Recipe {
+    Recipe {
+        result: result.to_owned(),
+        result_id: None,
+        amount,
+        ingredients: ingredients
+            .iter()
+            .map(|&(name, amount)| Ingredient { name: name.to_owned(), amount })
+            .collect(),
+        stations: stations
+            .iter()
+            .map(|&station| Station {
+                alternatives: vec![station.to_owned()],
+            })
+            .collect(),
+        conditions: Vec::new(),
+        version: None,
+    }
+}
+
+/// Indexes `recipes`, with the embedded pack loaded so their items can be found.
+pub fn graph(recipes: Vec<Recipe>) -> RecipeGraph {
+    pack::load_embedded();
+    RecipeGraph::from_recipes(recipes).unwrap()
+}
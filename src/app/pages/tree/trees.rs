@@ -0,0 +1,421 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize, de::IgnoredAny};
+
+use crate::{
+    app::storage_keys,
+    tree::{
+        History, Node, Tree,
+        file::{MilestoneFile, NodeFile},
+    },
+};
+
+/// All trees, one of which is shown at a time. There's always at least one.
+#[derive(Serialize)]
+pub struct Trees {
+    trees: Vec<Tree>,
+    active: usize,
+    /// Items of the stored trees that aren't in the loaded data packs, and were left out. The
+    /// trees aren't saved over while there are any, so they're back once the packs are.
+    #[serde(skip)]
+    missing: BTreeSet<String>,
+}
+
+/// How trees are read back from storage. Items are kept by name until they're looked up, like
+/// in tree files, so trees still load when some of their items aren't in the loaded data packs.
+/// Undo history refers to items directly, so when it can't be read, the trees are read again
+/// without it: only undo steps are lost that way, never the trees.
+#[derive(Deserialize)]
+#[serde(bound = "H: Deserialize<'de> + Default")]
+struct StoredTrees<H = History> {
+    trees: Vec<StoredTree<H>>,
+    active: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "H: Deserialize<'de> + Default")]
+struct StoredTree<H> {
+    name: String,
+    #[serde(default)]
+    milestones: Vec<MilestoneFile>,
+    /// The single root trees had before they were split into milestones. Moved into the first
+    /// milestone when loading.
+    #[serde(default)]
+    root: Option<NodeFile>,
+    #[serde(default)]
+    history: H,
+}
+
+impl StoredTrees {
+    fn get(storage: &dyn eframe::Storage) -> Option<Self> {
+        eframe::get_value(storage, storage_keys::TREES).or_else(|| {
+            let trees = eframe::get_value::<StoredTrees<IgnoredAny>>(storage, storage_keys::TREES)?;
+
+            Some(Self {
+                trees: trees
+                    .trees
+                    .into_iter()
+                    .map(|tree| StoredTree {
+                        name: tree.name,
+                        milestones: tree.milestones,
+                        root: tree.root,
+                        history: History::default(),
+                    })
+                    .collect(),
+                active: trees.active,
+            })
+        })
+    }
+}
+
+impl StoredTree<History> {
+    fn into_tree(self, missing: &mut BTreeSet<String>) -> Tree {
+        let milestones = self
+            .milestones
+            .into_iter()
+            .map(|milestone| milestone.into_milestone(missing))
+            .collect::<Vec<_>>();
+
+        if milestones.is_empty()
+            && let Some(root) = self.root
+        {
+            return Tree::new(self.name, root.into_node(missing));
+        }
+
+        Tree {
+            history: self.history,
+            ..Tree::from_milestones(self.name, milestones)
+        }
+    }
+}
+
+impl Trees {
+    /// Loads the trees from storage, moving over the single tree older versions saved. Needs the
+    /// data packs, since nodes refer to their items. Items that aren't in the packs are left out
+    /// and listed in [`Self::missing`].
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut missing = BTreeSet::new();
+
+        if let Some(trees) = storage
+            .and_then(StoredTrees::get)
+            .filter(|trees| !trees.trees.is_empty())
+        {
+            return Self {
+                active: trees.active.min(trees.trees.len() - 1),
+                trees: trees
+                    .trees
+                    .into_iter()
+                    .map(|tree| tree.into_tree(&mut missing))
+                    .collect(),
+                missing,
+            };
+        }
+
+        let root = match storage
+            .and_then(|storage| eframe::get_value::<NodeFile>(storage, storage_keys::TREE_ROOT))
+        {
+            Some(root) => root.into_node(&mut missing),
+            None => Node::from_name("Copper Pickaxe"),
+        };
+
+        Self {
+            trees: vec![Tree::new("Tree 1".to_owned(), root)],
+            active: 0,
+            missing,
+        }
+    }
+
+    /// Saves the trees, unless items were left out when loading them. Saving then would lose
+    /// those items for good.
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        if self.missing.is_empty() {
+            eframe::set_value(storage, storage_keys::TREES, self);
+        }
+    }
+
+    /// Items of the stored trees that were left out because they aren't in the loaded packs.
+    pub fn missing(&self) -> &BTreeSet<String> {
+        &self.missing
+    }
+
+    /// Lets the trees be saved without the items that were left out when loading them.
+    pub fn forget_missing(&mut self) {
+        self.missing.clear();
+    }
+
+    pub fn active(&self) -> &Tree {
+        &self.trees[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Tree {
+        &mut self.trees[self.active]
+    }
+
+    pub fn create(&mut self) {
+        let name = self.unused_name("Tree");
+        self.trees.push(Tree::new(name, None));
+        self.active = self.trees.len() - 1;
+    }
+
+    /// Adds a tree, like one opened from a file, and shows it. It keeps its name unless another
+    /// tree already has it.
+    pub fn add(&mut self, mut tree: Tree) {
+        if self.trees.iter().any(|t| t.name == tree.name) {
+            tree.name = self.unused_name(&tree.name);
+        }
+
+        self.trees.push(tree);
+        self.active = self.trees.len() - 1;
+    }
+
+    pub fn duplicate(&mut self, index: usize) {
+        let mut tree = self.trees[index].clone();
+        tree.name = self.unused_name(&format!("{} copy", tree.name));
+        tree.history = History::default();
+
+        self.trees.insert(index + 1, tree);
+        self.active = index + 1;
+    }
+
+    /// Deletes a tree, replacing it with an empty one if it was the last.
+    pub fn delete(&mut self, index: usize) {
+        self.trees.remove(index);
+
+        if self.trees.is_empty() {
+            self.create();
+        } else if self.active >= index && self.active > 0 {
+            self.active -= 1;
+        }
+    }
+
+    /// `base` followed by the lowest number that makes it unique, like "Tree 2".
+    fn unused_name(&self, base: &str) -> String {
+        (1..)
+            .map(|n| format!("{base} {n}"))
+            .find(|name| !self.trees.iter().any(|t| &t.name == name))
+            .unwrap()
+    }
+}
+
+/// Draws a tab for each tree to switch between them, with a context menu to rename, duplicate or
+/// delete it, and a button to create a new one.
+pub fn tab_bar(ui: &mut egui::Ui, trees: &mut Trees) {
+    let mut duplicate = None;
+    let mut delete = None;
+
+    ui.horizontal_wrapped(|ui| {
+        for (index, tree) in trees.trees.iter_mut().enumerate() {
+            let res = ui.selectable_label(index == trees.active, &tree.name);
+
+            if res.clicked() {
+                trees.active = index;
+            }
+
+            res.context_menu(|ui| {
+                let res = ui.text_edit_singleline(&mut tree.name);
+                if ui.memory(|m| m.focused().is_none()) {
+                    res.request_focus();
+                }
+                if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    ui.close();
+                }
+
+                if ui.button("Duplicate").clicked() {
+                    duplicate = Some(index);
+                }
+
+                if ui.button("Delete").clicked() {
+                    delete = Some(index);
+                }
+            });
+        }
+
+        if ui.button("+").on_hover_text("New tree").clicked() {
+            trees.create();
+        }
+    });
+
+    if let Some(index) = duplicate {
+        trees.duplicate(index);
+    }
+
+    if let Some(index) = delete {
+        trees.delete(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::pack;
+
+    #[derive(Default)]
+    struct Storage(HashMap<String, String>);
+
+    impl eframe::Storage for Storage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn storage(key: &str, value: &str) -> Storage {
+        Storage(HashMap::from([(key.to_owned(), value.to_owned())]))
+    }
+
+    fn names(trees: &Trees) -> Vec<&str> {
+        trees.trees.iter().map(|tree| tree.name.as_str()).collect()
+    }
+
+    fn root_items(tree: &Tree) -> Vec<&str> {
+        tree.roots().map(|root| root.item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn nothing_stored() {
+        pack::load_embedded();
+
+        let trees = Trees::load(None);
+        assert_eq!(names(&trees), ["Tree 1"]);
+        assert_eq!(root_items(trees.active()), ["Copper Pickaxe"]);
+        assert!(trees.missing().is_empty());
+    }
+
+    #[test]
+    fn migrates_the_single_tree() {
+        pack::load_embedded();
+
+        let storage = storage(
+            storage_keys::TREE_ROOT,
+            r#"(item: "Copper Pickaxe", obtained: true, next: Some((item: "Wood", amount: 3,
+                next: None, children: [])), children: [(item: "Not An Item", next: None,
+                children: [])])"#,
+        );
+
+        let trees = Trees::load(Some(&storage));
+        assert_eq!(names(&trees), ["Tree 1"]);
+        assert_eq!(trees.missing(), &BTreeSet::from(["Not An Item".to_owned()]));
+
+        let root = trees.active().roots().next().unwrap();
+        assert_eq!(root.item.name, "Copper Pickaxe");
+        assert!(root.children.is_empty());
+        assert_eq!(root.next.as_ref().unwrap().amount, 3);
+    }
+
+    #[test]
+    fn migrates_trees_without_milestones() {
+        pack::load_embedded();
+
+        let storage = storage(
+            storage_keys::TREES,
+            r#"(trees: [(name: "Old", root: Some((item: "Wood", next: None, children: [])))],
+                active: 3)"#,
+        );
+
+        let trees = Trees::load(Some(&storage));
+        assert_eq!(names(&trees), ["Old"]);
+        assert_eq!(trees.active, 0);
+        assert_eq!(root_items(trees.active()), ["Wood"]);
+    }
+
+    #[test]
+    fn keeps_history() {
+        pack::load_embedded();
+
+        let mut trees = Trees::load(None);
+        trees
+            .active_mut()
+            .add_item(pack::get().item_by_name("Wood").unwrap());
+
+        let mut storage = Storage::default();
+        trees.save(&mut storage);
+
+        let mut trees = Trees::load(Some(&storage));
+        assert_eq!(root_items(trees.active()), ["Copper Pickaxe", "Wood"]);
+
+        trees.active_mut().undo();
+        assert_eq!(root_items(trees.active()), ["Copper Pickaxe"]);
+    }
+
+    #[test]
+    fn doesnt_save_over_missing_items() {
+        pack::load_embedded();
+
+        let mut trees = Trees::load(None);
+        trees
+            .active_mut()
+            .add_item(pack::get().item_by_name("Wood").unwrap());
+        trees.create();
+
+        let mut storage = Storage::default();
+        trees.save(&mut storage);
+
+        // As if Wood came from a pack that isn't loaded this time.
+        let stored = storage.0[storage_keys::TREES].replace(r#""Wood""#, r#""Not An Item""#);
+        let mut storage = self::storage(storage_keys::TREES, &stored);
+
+        let mut trees = Trees::load(Some(&storage));
+        assert_eq!(names(&trees), ["Tree 1", "Tree 2"]);
+        assert_eq!(root_items(&trees.trees[0]), ["Copper Pickaxe"]);
+        assert!(!trees.trees[0].history.can_undo());
+        assert_eq!(trees.missing(), &BTreeSet::from(["Not An Item".to_owned()]));
+
+        trees.delete(0);
+        trees.save(&mut storage);
+        assert_eq!(storage.0[storage_keys::TREES], stored);
+
+        trees.forget_missing();
+        trees.save(&mut storage);
+        assert_eq!(names(&Trees::load(Some(&storage))), ["Tree 2"]);
+    }
+
+    #[test]
+    fn create_duplicate_delete() {
+        pack::load_embedded();
+
+        let mut trees = Trees::load(None);
+        trees.create();
+        assert_eq!(names(&trees), ["Tree 1", "Tree 2"]);
+        assert_eq!(trees.active, 1);
+        assert!(trees.active().roots().next().is_none());
+
+        trees.duplicate(0);
+        assert_eq!(names(&trees), ["Tree 1", "Tree 1 copy 1", "Tree 2"]);
+        assert_eq!(trees.active, 1);
+        assert_eq!(root_items(trees.active()), ["Copper Pickaxe"]);
+
+        trees.duplicate(0);
+        assert_eq!(names(&trees)[1], "Tree 1 copy 2");
+
+        trees.delete(0);
+        assert_eq!(names(&trees), ["Tree 1 copy 2", "Tree 1 copy 1", "Tree 2"]);
+        assert_eq!(trees.active, 0);
+
+        trees.delete(2);
+        trees.delete(1);
+        trees.delete(0);
+        assert_eq!(names(&trees), ["Tree 1"]);
+        assert_eq!(trees.active, 0);
+    }
+
+    #[test]
+    fn unused_name() {
+        pack::load_embedded();
+
+        let mut trees = Trees::load(None);
+        assert_eq!(trees.unused_name("Tree"), "Tree 2");
+        assert_eq!(trees.unused_name("Plan"), "Plan 1");
+
+        trees.add(Tree::from_milestones("Tree 1".to_owned(), Vec::new()));
+        trees.add(Tree::from_milestones("Plan".to_owned(), Vec::new()));
+        assert_eq!(names(&trees), ["Tree 1", "Tree 1 1", "Plan"]);
+        assert_eq!(trees.unused_name("Tree 1"), "Tree 1 2");
+    }
+}
@@ -0,0 +1,35 @@
+use egui::{Image, Widget as _, vec2};
+use wiki_data::item::Coins;
+
+/// Draws an amount of money as counts next to coin icons, like the wiki does.
+pub fn ui(ui: &mut egui::Ui, coins: Coins) -> egui::Response {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 1.;
+
+        if coins.is_zero() {
+            denomination(ui, 0, "Copper Coin.png");
+        }
+
+        let denominations = [
+            (coins.platinum(), "Platinum Coin.png"),
+            (coins.gold(), "Gold Coin.png"),
+            (coins.silver(), "Silver Coin.png"),
+            (coins.copper(), "Copper Coin.png"),
+        ];
+
+        for (amount, image) in denominations {
+            if amount > 0 {
+                denomination(ui, amount, image);
+            }
+        }
+    })
+    .response
+}
+
+fn denomination(ui: &mut egui::Ui, amount: u64, image: &str) {
+    ui.label(egui::RichText::new(amount.to_string()).small());
+    Image::new(format!("wiki://{image}"))
+        .fit_to_exact_size(vec2(12., 12.))
+        .ui(ui);
+    ui.add_space(3.);
+}
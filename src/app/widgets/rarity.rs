@@ -0,0 +1,18 @@
+use egui::{Color32, epaint::Hsva};
+use wiki_data::item::{Rarity, RarityColor};
+
+/// The color item names of this rarity are drawn in. Expert and Master items cycle through colors
+/// like they do in game.
+pub fn color(ui: &egui::Ui, rarity: Rarity) -> Color32 {
+    match rarity.color() {
+        RarityColor::Static { r, g, b } => Color32::from_rgb(r, g, b),
+        RarityColor::Expert => {
+            ui.ctx().request_repaint();
+            Hsva::new(((ui.input(|i| i.time) / 2.) % 1.) as f32, 1., 1., 1.).into()
+        }
+        RarityColor::Master => {
+            ui.ctx().request_repaint();
+            Hsva::new((((ui.input(|i| i.time) / 2.) % 0.3) as f32 - 0.15).abs(), 1., 1., 1.).into()
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use egui::{Color32, Image, RichText, Widget as _, vec2};
+use wiki_data::{
+    ArchivedItem, DataPack,
+    item::{ArchivedItemType, ArchivedSpan, ArchivedStyle, ArchivedWikiText, Coins},
+};
+
+use super::{coins, rarity};
+use crate::pack;
+
+/// Draws the tooltip of an item the way the game does: its name in the color of its rarity, its
+/// stats, the tooltip text from the wiki and its sell price.
+pub fn ui(ui: &mut egui::Ui, item: &ArchivedItem) {
+    ui.set_max_width(320.);
+    ui.spacing_mut().item_spacing.y = 1.;
+
+    ui.label(
+        RichText::new(item.name.as_str())
+            .strong()
+            .color(rarity::color(ui, item.rarity())),
+    );
+
+    for line in stat_lines(item) {
+        ui.label(line);
+    }
+
+    if let Some(tooltip) = item.tooltip.as_ref() {
+        wikitext(ui, tooltip, pack::get().source(item));
+    }
+
+    if let Some(sell) = item.sell.as_ref() {
+        ui.horizontal(|ui| {
+            ui.label("Sell");
+            coins::ui(ui, Coins::from(sell));
+        });
+    }
+}
+
+/// Draws wiki text line by line, keeping its colors, emphasis, links and inline images. Links
+/// can't be followed from a tooltip, so they're only colored like links. Images are looked up in
+/// `pack` first, the pack the text comes from.
+pub fn wikitext(ui: &mut egui::Ui, text: &ArchivedWikiText, pack: Option<&DataPack>) {
+    for line in text.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.;
+
+            for span in line {
+                match span {
+                    ArchivedSpan::Text { text, style } => {
+                        ui.label(styled(text, style, None));
+                    }
+                    ArchivedSpan::Link { text, style, .. } => {
+                        ui.label(styled(text, style, Some(ui.visuals().hyperlink_color)));
+                    }
+                    ArchivedSpan::Image { file } => {
+                        let file = pack::get().qualified_image_name(pack, file);
+                        Image::new(format!("wiki://{file}"))
+                            .fit_to_exact_size(vec2(16., 16.))
+                            .ui(ui);
+                    }
+                    ArchivedSpan::LineBreak => {}
+                }
+            }
+        });
+    }
+}
+
+fn styled(text: &str, style: &ArchivedStyle, default_color: Option<Color32>) -> RichText {
+    let mut text = RichText::new(text);
+
+    if style.bold {
+        text = text.strong();
+    }
+    if style.italic {
+        text = text.italics();
+    }
+    if let Some([r, g, b]) = style.color() {
+        text = text.color(Color32::from_rgb(r, g, b));
+    } else if let Some(color) = default_color {
+        text = text.color(color);
+    }
+
+    text
+}
+
+/// The stat lines the game shows above the tooltip text, in the same order.
+fn stat_lines(item: &ArchivedItem) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if let Some(damage) = item.damage.as_ref() {
+        let ty = item
+            .damage_type
+            .as_ref()
+            .map(|t| format!("{t} ").to_lowercase())
+            .unwrap_or_default();
+        lines.push(format!("{damage} {ty}damage"));
+    }
+    if let Some(critical) = item.critical.as_ref() {
+        lines.push(format!("{critical}% critical strike chance"));
+    }
+    if let Some(usetime) = item.usetime.as_ref()
+        && item.damage.is_some()
+    {
+        lines.push(speed(usetime.to_native()).to_owned());
+    }
+    if let Some(knockback) = item.knockback.as_ref() {
+        lines.push(knockback_strength(knockback.to_native()).to_owned());
+    }
+    for (power, kind) in item.powers() {
+        lines.push(format!("{power}% {kind} power"));
+    }
+    if let Some(bonus) = item.bonus.as_ref() {
+        lines.push(format!("{:+} range", bonus.to_native()));
+    }
+    if let Some(defense) = item.defense.as_ref() {
+        lines.push(format!("{defense} defense"));
+    }
+    if item
+        .types
+        .iter()
+        .any(|t| matches!(t, ArchivedItemType::Accessory))
+    {
+        lines.push("Equipable".to_owned());
+    }
+    if item
+        .types
+        .iter()
+        .any(|t| matches!(t, ArchivedItemType::Placeable))
+    {
+        lines.push("Can be placed".to_owned());
+    }
+    if item.consumable.as_ref().is_some_and(|c| *c) {
+        lines.push("Consumable".to_owned());
+    }
+    if item
+        .types
+        .iter()
+        .any(|t| matches!(t, ArchivedItemType::Material))
+    {
+        lines.push("Material".to_owned());
+    }
+    if let Some(mana) = item.mana.as_ref() {
+        lines.push(format!("Uses {mana} mana"));
+    }
+    if let Some(hheal) = item.hheal.as_ref() {
+        lines.push(format!("Restores {hheal} life"));
+    }
+    if let Some(mheal) = item.mheal.as_ref() {
+        lines.push(format!("Restores {mheal} mana"));
+    }
+
+    lines
+}
+
+/// How the game describes a use time, in frames.
+fn speed(usetime: i32) -> &'static str {
+    match usetime {
+        ..=8 => "Insanely fast speed",
+        9..=20 => "Very fast speed",
+        21..=25 => "Fast speed",
+        26..=30 => "Average speed",
+        31..=35 => "Slow speed",
+        36..=45 => "Very slow speed",
+        46..=55 => "Extremely slow speed",
+        _ => "Snail speed",
+    }
+}
+
+/// How the game describes a knockback value.
+fn knockback_strength(knockback: f32) -> &'static str {
+    match knockback {
+        k if k <= 0. => "No knockback",
+        k if k <= 1.5 => "Extremely weak knockback",
+        k if k <= 3. => "Very weak knockback",
+        k if k <= 4. => "Weak knockback",
+        k if k <= 6. => "Average knockback",
+        k if k <= 7. => "Strong knockback",
+        k if k <= 9. => "Very strong knockback",
+        k if k <= 11. => "Extremely strong knockback",
+        _ => "Insane knockback",
+    }
+}
@@ -0,0 +1,292 @@
+//! Subcommands of the `terratree` binary that work on tree files without opening a window.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use wiki_data::item::Rarity;
+
+use crate::{
+    pack,
+    tree::{Node, Tree, file, render},
+};
+
+#[derive(clap::Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    /// Data packs to open the app with: `embedded` for the data built into the app, a pack file
+    /// or a URL. Defaults to the embedded data.
+    pub packs: Vec<String>,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Prints a tree file as indented text.
+    Print {
+        #[clap(flatten)]
+        tree: TreeArgs,
+    },
+    /// Checks that every item in a tree file exists in the data packs. Exits with an error if
+    /// any doesn't.
+    Validate {
+        #[clap(flatten)]
+        tree: TreeArgs,
+    },
+    /// Counts the items of a tree file by rarity and by whether they're from hardmode.
+    Stats {
+        #[clap(flatten)]
+        tree: TreeArgs,
+    },
+    /// Rewrites a tree file in another format, picked by the output's extension: TOML, or JSON
+    /// for `.json`. Items are kept by name, so this doesn't need the data packs.
+    Convert { input: PathBuf, output: PathBuf },
+    /// Renders a tree file to an image, as SVG or PNG depending on the output's extension.
+    Export {
+        #[clap(flatten)]
+        tree: TreeArgs,
+
+        /// Where to write the image.
+        output: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct TreeArgs {
+    /// The tree file, in TOML or JSON.
+    tree: PathBuf,
+
+    /// Data packs to look items up in, like for the app. Defaults to the embedded data.
+    #[clap(long = "pack")]
+    packs: Vec<String>,
+}
+
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Print { tree } => print!("{}", outline(&tree.load()?)),
+        Command::Validate { tree } => {
+            let path = tree.tree.clone();
+            let (tree, missing) = tree.load_with_missing()?;
+
+            for item in &missing {
+                eprintln!("{}: item {item:?} not found", path.display());
+            }
+
+            if !missing.is_empty() {
+                anyhow::bail!("{}: {} missing items", path.display(), missing.len());
+            }
+
+            println!(
+                "{}: all {} items found",
+                path.display(),
+                tree.roots().map(count).sum::<usize>()
+            );
+        }
+        Command::Stats { tree } => print!("{}", stats(&tree.load()?)),
+        Command::Convert { input, output } => {
+            let contents = read_to_string(&input)?;
+            let converted = file::convert(
+                &contents,
+                file::Format::of(&input.to_string_lossy()),
+                file::Format::of(&output.to_string_lossy()),
+            )
+            .with_context(|| format!("failed to read {}", input.display()))?;
+
+            write(&output, converted)?;
+        }
+        Command::Export { tree, output } => {
+            let image = render::export(&tree.load()?, &output.to_string_lossy())?;
+            write(&output, image)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl TreeArgs {
+    /// Loads the packs and reads the tree, warning about items that aren't in the packs.
+    fn load(self) -> anyhow::Result<Tree> {
+        let path = self.tree.clone();
+        let (tree, missing) = self.load_with_missing()?;
+
+        for item in missing {
+            eprintln!("warning: {}: item {item:?} not found, left out", path.display());
+        }
+
+        Ok(tree)
+    }
+
+    /// Loads the packs and reads the tree, along with the names of items that were left out.
+    fn load_with_missing(self) -> anyhow::Result<(Tree, Vec<String>)> {
+        pack::load_blocking(self.packs)
+            .map_err(|e| anyhow::anyhow!("failed to load data pack: {e}"))?;
+
+        let contents = read_to_string(&self.tree)?;
+        let (tree, missing) = file::read(&contents, file::Format::of(&self.tree.to_string_lossy()))
+            .with_context(|| format!("failed to read {}", self.tree.display()))?;
+
+        Ok((tree, missing.into_iter().collect()))
+    }
+}
+
+fn read_to_string(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// The number of nodes in a node, everything branching off it and the nodes after it.
+fn count(node: &Node) -> usize {
+    nodes(node).count()
+}
+
+/// A node, everything branching off it and the nodes after it, depth first.
+fn nodes(node: &Node) -> Box<dyn Iterator<Item = &Node> + '_> {
+    Box::new(
+        std::iter::once(node)
+            .chain(node.children.iter().flat_map(nodes))
+            .chain(node.next.as_deref().into_iter().flat_map(nodes)),
+    )
+}
+
+/// The tree as indented text: each milestone, then its nodes with their children indented below
+/// them, like "12 × Copper Bar [obtained]".
+fn outline(tree: &Tree) -> String {
+    fn lines(out: &mut String, node: &Node, depth: usize) {
+        let mut next = Some(node);
+
+        while let Some(node) = next {
+            write!(out, "{:indent$}", "", indent = depth * 2).ok();
+            if node.amount > 1 {
+                write!(out, "{} × ", node.amount).ok();
+            }
+            write!(out, "{}", node.item.name).ok();
+            if !node.status.is_planned() {
+                write!(out, " [{}]", node.status.label().to_lowercase()).ok();
+            }
+            out.push('\n');
+
+            for child in &node.children {
+                lines(out, child, depth + 1);
+            }
+
+            next = node.next.as_deref();
+        }
+    }
+
+    let mut out = format!("{}\n", tree.name);
+
+    for milestone in &tree.milestones {
+        writeln!(out, "\n{}", milestone.title).ok();
+        for root in &milestone.roots {
+            lines(&mut out, root, 1);
+        }
+    }
+
+    out
+}
+
+/// Counts of the nodes of a tree by rarity and by whether their item is from hardmode.
+fn stats(tree: &Tree) -> String {
+    let mut rarities = BTreeMap::<Rarity, usize>::new();
+    let mut hardmode = BTreeMap::<&str, usize>::new();
+    let mut total = 0;
+
+    for node in tree.roots().flat_map(nodes) {
+        total += 1;
+        *rarities.entry(node.item.rarity()).or_default() += 1;
+
+        let stage = match node.item.hardmode.as_ref() {
+            Some(true) => "Hardmode",
+            Some(false) => "Pre-Hardmode",
+            None => "Unknown",
+        };
+        *hardmode.entry(stage).or_default() += 1;
+    }
+
+    let mut out = format!("{}: {total} items\n", tree.name);
+
+    out.push_str("\nBy rarity:\n");
+    for (rarity, count) in rarities {
+        writeln!(out, "  {:<12} {count:>4}", format!("{rarity:?}")).ok();
+    }
+
+    out.push_str("\nBy stage:\n");
+    for stage in ["Pre-Hardmode", "Hardmode", "Unknown"] {
+        if let Some(count) = hardmode.get(stage) {
+            writeln!(out, "  {stage:<12} {count:>4}").ok();
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::Status;
+
+    fn tree() -> Tree {
+        pack::load_embedded();
+
+        let contents = r#"
+            version = 1
+            name = "Plan"
+
+            [[milestones]]
+            title = "Start"
+
+            [[milestones.roots]]
+            item = "Copper Pickaxe"
+            status = "obtained"
+
+            [[milestones.roots.children]]
+            item = "Copper Bar"
+            amount = 12
+
+            [milestones.roots.next]
+            item = "Wood"
+            amount = 3
+        "#;
+
+        file::read(contents, file::Format::Toml).unwrap().0
+    }
+
+    #[test]
+    fn print() {
+        assert_eq!(
+            outline(&tree()),
+            "Plan\n\nStart\n  Copper Pickaxe [obtained]\n    12 × Copper Bar\n  3 × Wood\n",
+        );
+    }
+
+    #[test]
+    fn counts() {
+        let tree = tree();
+        assert_eq!(tree.roots().map(count).sum::<usize>(), 3);
+        assert_eq!(tree.milestones[0].roots[0].status, Status::Obtained);
+
+        let stats = stats(&tree);
+        assert!(stats.starts_with("Plan: 3 items\n"));
+        assert!(stats.contains("By rarity:"));
+    }
+
+    #[test]
+    fn convert() {
+        let toml = "version = 1\nname = \"Plan\"\n\n[[milestones]]\ntitle = \"Start\"\n\n\
+                    [[milestones.roots]]\nitem = \"Not An Item\"\n";
+
+        let json = file::convert(toml, file::Format::Toml, file::Format::Json).unwrap();
+        assert!(json.contains(r#""item": "Not An Item""#));
+
+        let back = file::convert(&json, file::Format::Json, file::Format::Toml).unwrap();
+        assert_eq!(back.trim(), toml.trim());
+    }
+}
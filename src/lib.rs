@@ -1,6 +1,12 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod pack;
+mod tdata;
+mod tree;
 mod wiki_img;
 
 mod app;
 pub use app::TerratreeApp;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
@@ -4,8 +4,28 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
+    use clap::Parser as _;
+
+    // Release builds have no console of their own on Windows, so subcommands and `--help` print
+    // to the one they were started from.
+    #[cfg(all(windows, not(debug_assertions)))]
+    if std::env::args_os().len() > 1 {
+        attach_console();
+    }
+
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    let args = terratree::cli::Args::parse();
+
+    // Subcommands run headless, without a window:
+    if let Some(command) = args.command {
+        if let Err(e) = terratree::cli::run(command) {
+            eprintln!("error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -20,10 +40,27 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "terratree",
         native_options,
-        Box::new(|cc| Ok(Box::new(terratree::TerratreeApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(terratree::TerratreeApp::with_packs(cc, args.packs)))),
     )
 }
 
+/// Attaches to the console of the process that started this one, if it has one.
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // SAFETY: `AttachConsole` takes no pointers, and just fails if there's no console to attach
+    // to or one is attached already.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -0,0 +1,368 @@
+use std::{
+    borrow::Cow,
+    sync::{OnceLock, mpsc},
+};
+
+use wiki_data::{
+    ArchivedItem, ArchivedRecipe, DataPack, image::ArchivedImage, recipe::RecipeGraph,
+};
+
+static PACKS: OnceLock<Packs> = OnceLock::new();
+
+/// The loaded data packs. `TerratreeApp` doesn't draw anything that needs item data until loading
+/// has finished, so this is safe to call from the pages.
+pub fn get() -> &'static Packs {
+    PACKS.get().expect("data packs are not loaded yet")
+}
+
+pub fn try_get() -> Option<&'static Packs> {
+    PACKS.get()
+}
+
+/// Loads the given packs, or the embedded one if none are given, and waits for them. For the
+/// command line, where there's no frame loop to poll the [`Loader`] from.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_blocking(packs: Vec<String>) -> Result<(), String> {
+    let mut loader = Loader::start(Source::from_args(packs), &egui::Context::default());
+    loop {
+        if let Some(result) = loader.poll() {
+            return result;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+/// Installs the embedded data pack, for tests that need item data.
+#[cfg(test)]
+pub fn load_embedded() {
+    if try_get().is_none() {
+        Loader::start(vec![Source::Embedded], &egui::Context::default()).poll();
+    }
+}
+
+/// Every loaded data pack, in the order they were given. Lookups go through the packs in order,
+/// so a modded pack can use items from the base game pack before it.
+pub struct Packs(Vec<DataPack>);
+
+impl Packs {
+    pub fn iter(&self) -> std::slice::Iter<'_, DataPack> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// All items of all packs, along with the pack they come from.
+    pub fn items(
+        &'static self,
+    ) -> impl Iterator<Item = (&'static DataPack, &'static ArchivedItem)> {
+        self.iter()
+            .flat_map(|pack| pack.items.iter().map(move |item| (pack, item)))
+    }
+
+    /// Looks up an item by its display name. The name may be qualified with a pack namespace,
+    /// like "calamity:Murasama", to look in that pack only.
+    pub fn item_by_name(&self, name: &str) -> Option<&'static ArchivedItem> {
+        if let Some((namespace, name)) = name.split_once(':')
+            && let Some(pack) = self.iter().find(|p| p.namespace == namespace)
+        {
+            return pack.items.by_name(name);
+        }
+
+        self.iter().find_map(|pack| pack.items.by_name(name))
+    }
+
+    /// The name that [`Self::item_by_name`] resolves back to `item`. This is the plain name,
+    /// unless an earlier pack has an item with the same name, so it's only allocated then.
+    pub fn qualified_name(&self, item: &'static ArchivedItem) -> Cow<'static, str> {
+        match self.source(item) {
+            Some(pack)
+                if !self
+                    .item_by_name(&item.name)
+                    .is_some_and(|found| std::ptr::eq(found, item)) =>
+            {
+                Cow::Owned(format!("{}:{}", pack.namespace, item.name))
+            }
+            _ => Cow::Borrowed(item.name.as_str()),
+        }
+    }
+
+    /// The pack that `item` belongs to.
+    pub fn source(&self, item: &ArchivedItem) -> Option<&DataPack> {
+        self.iter().find(|pack| {
+            pack.items
+                .by_name(&item.name)
+                .is_some_and(|found| std::ptr::eq(found, item))
+        })
+    }
+
+    /// Recipes from all packs whose result is the item with the given name.
+    pub fn producing(&self, name: &str) -> impl Iterator<Item = &'static ArchivedRecipe> {
+        self.iter()
+            .flat_map(move |pack| pack.recipes.producing(name))
+    }
+
+    /// Looks up an image by its file name. Like item names, the name may be qualified with a pack
+    /// namespace, like "calamity:Murasama.png", to look in that pack only.
+    pub fn image_by_name(&self, name: &str) -> Option<&'static ArchivedImage> {
+        if let Some((namespace, name)) = name.split_once(':')
+            && let Some(pack) = self.iter().find(|p| p.namespace == namespace)
+        {
+            return pack.images.by_name(name);
+        }
+
+        self.iter().find_map(|pack| pack.images.by_name(name))
+    }
+
+    /// The name that [`Self::image_by_name`] resolves to the icon of `item` from its own pack.
+    pub fn image_name(&self, item: &ArchivedItem) -> Option<String> {
+        let file = item.image_location.as_ref()?.name.as_str();
+        Some(self.qualified_image_name(self.source(item), file))
+    }
+
+    /// The name that [`Self::image_by_name`] resolves to the image `file` of `pack`. This is the
+    /// plain file name, unless an earlier pack has an image with the same name. Images that
+    /// `pack` doesn't have are looked up in all packs.
+    pub fn qualified_image_name(&self, pack: Option<&DataPack>, file: &str) -> String {
+        match pack.and_then(|pack| Some((pack, pack.images.by_name(file)?))) {
+            Some((pack, image))
+                if !self
+                    .image_by_name(file)
+                    .is_some_and(|found| std::ptr::eq(found, image)) =>
+            {
+                format!("{}:{file}", pack.namespace)
+            }
+            _ => file.to_owned(),
+        }
+    }
+}
+
+/// Where recipes are looked up: all loaded packs in the app, or a single graph in tests.
+pub trait RecipeSource {
+    /// Recipes whose result is the item with the given name.
+    fn producing(&self, name: &str) -> impl Iterator<Item = &'static ArchivedRecipe>;
+}
+
+impl RecipeSource for Packs {
+    fn producing(&self, name: &str) -> impl Iterator<Item = &'static ArchivedRecipe> {
+        Packs::producing(self, name)
+    }
+}
+
+impl RecipeSource for RecipeGraph {
+    fn producing(&self, name: &str) -> impl Iterator<Item = &'static ArchivedRecipe> {
+        RecipeGraph::producing(self, name)
+    }
+}
+
+/// Where the web build fetches the base game pack from, next to the page. Trunk builds it from the
+/// embedded archives, see `Trunk.toml`.
+#[cfg(target_arch = "wasm32")]
+const DEFAULT_PACK_URL: &str = "terraria.pack";
+
+/// Where to load a data pack from.
+pub enum Source {
+    /// The data compiled into wiki-data. Only native builds embed it.
+    #[cfg(not(target_arch = "wasm32"))]
+    Embedded,
+    /// A pack file on disk.
+    #[cfg(not(target_arch = "wasm32"))]
+    Path(std::path::PathBuf),
+    /// A pack file fetched over HTTP.
+    Url(String),
+}
+
+impl Source {
+    /// Picks the packs from the command line on native, or from the `pack` query parameters on
+    /// web. `embedded` stands for the base game data, which is also what is loaded when no packs
+    /// are given. Native builds have it compiled in, web builds fetch it.
+    pub fn from_args(args: Vec<String>) -> Vec<Self> {
+        if args.is_empty() {
+            return vec![Self::default()];
+        }
+
+        args.into_iter().map(Self::parse).collect()
+    }
+
+    /// The packs in the `pack` query parameters of the page, see [`Source::from_args`].
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_query(cc: &eframe::CreationContext<'_>) -> Vec<Self> {
+        let args = cc
+            .integration_info
+            .web_info
+            .location
+            .query_map
+            .get("pack")
+            .cloned()
+            .unwrap_or_default();
+
+        Self::from_args(args)
+    }
+
+    fn parse(arg: String) -> Self {
+        match arg {
+            arg if arg == "embedded" => Self::default(),
+            arg if arg.starts_with("http://") || arg.starts_with("https://") => Self::Url(arg),
+            #[cfg(not(target_arch = "wasm32"))]
+            arg => Self::Path(arg.into()),
+            #[cfg(target_arch = "wasm32")]
+            arg => Self::Url(arg),
+        }
+    }
+}
+
+/// The base game data.
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for Source {
+    fn default() -> Self {
+        Self::Embedded
+    }
+}
+
+/// The base game data.
+#[cfg(target_arch = "wasm32")]
+impl Default for Source {
+    fn default() -> Self {
+        Self::Url(DEFAULT_PACK_URL.to_owned())
+    }
+}
+
+/// Data packs that are being loaded in the background.
+pub struct Loader {
+    rx: mpsc::Receiver<(usize, Result<DataPack, String>)>,
+    packs: Vec<Option<DataPack>>,
+}
+
+impl Loader {
+    pub fn start(sources: Vec<Source>, ctx: &egui::Context) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let packs = sources.iter().map(|_| None).collect();
+
+        for (i, source) in sources.into_iter().enumerate() {
+            let tx = tx.clone();
+            let ctx = ctx.clone();
+            let done = move |res: Result<DataPack, String>| {
+                tx.send((i, res)).ok();
+                ctx.request_repaint();
+            };
+
+            match source {
+                #[cfg(not(target_arch = "wasm32"))]
+                Source::Embedded => done(Ok(DataPack::embedded())),
+                #[cfg(not(target_arch = "wasm32"))]
+                Source::Path(path) => {
+                    std::thread::spawn(move || {
+                        done(
+                            DataPack::from_path(&path)
+                                .map_err(|e| format!("{}: {e}", path.display())),
+                        );
+                    });
+                }
+                Source::Url(url) => {
+                    ehttp::fetch(ehttp::Request::get(&url), move |res| {
+                        done(match res {
+                            Ok(res) if res.ok => {
+                                DataPack::from_bytes(&res.bytes).map_err(|e| format!("{url}: {e}"))
+                            }
+                            Ok(res) => Err(format!("{url}: {} {}", res.status, res.status_text)),
+                            Err(e) => Err(format!("{url}: {e}")),
+                        });
+                    });
+                }
+            }
+        }
+
+        Self { rx, packs }
+    }
+
+    /// Returns `None` while packs are still loading. Once all of them have loaded, they are
+    /// installed as the packs returned by [`get`].
+    pub fn poll(&mut self) -> Option<Result<(), String>> {
+        loop {
+            match self.rx.try_recv() {
+                Ok((i, Ok(pack))) => {
+                    log::info!("loaded data pack {:?} with {} items", pack.name, pack.items.len());
+                    self.packs[i] = Some(pack);
+                }
+                Ok((_, Err(e))) => return Some(Err(e)),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if self.packs.iter().any(Option::is_none) {
+                        return Some(Err("data pack loader stopped".into()));
+                    }
+                    break;
+                }
+            }
+        }
+
+        if self.packs.iter().any(Option::is_none) {
+            return None;
+        }
+
+        let packs = self.packs.drain(..).flatten().collect();
+        PACKS.set(Packs(packs)).ok();
+        Some(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiki_data::{Image, Item, Pack};
+
+    use super::*;
+
+    /// The embedded pack, followed by a mod that has its own Copper Pickaxe with its own icon,
+    /// and a Murasama that uses the base game's Wood icon.
+    fn packs() -> Packs {
+        let item = |name: &str, image: &str| {
+            serde_json::from_value::<Item>(serde_json::json!({
+                "item_id": 1,
+                "name": name,
+                "image_location": { "name": image, "url": "", "width": 1, "height": 1 },
+                "types": [],
+                "rarity": "White",
+            }))
+            .unwrap()
+        };
+
+        let pack = Pack {
+            namespace: "mod".to_owned(),
+            name: "Mod".to_owned(),
+            wiki_url: "https://example.com/wiki/".to_owned(),
+            items: vec![item("Copper Pickaxe", "Copper Pickaxe.png"), item("Murasama", "Wood.png")],
+            recipes: Vec::new(),
+            images: vec![Image {
+                name: "Copper Pickaxe.png".to_owned(),
+                data: vec![1, 2, 3],
+                width: 1,
+                height: 1,
+            }],
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&pack).unwrap();
+
+        Packs(vec![DataPack::embedded(), DataPack::from_bytes(&bytes).unwrap()])
+    }
+
+    #[test]
+    fn images_come_from_the_items_pack() {
+        let packs = packs();
+
+        let base = packs.item_by_name("Copper Pickaxe").unwrap();
+        let modded = packs.item_by_name("mod:Copper Pickaxe").unwrap();
+        assert!(!std::ptr::eq(base, modded));
+
+        assert_eq!(packs.image_name(base).unwrap(), "Copper Pickaxe.png");
+        assert_eq!(packs.image_name(modded).unwrap(), "mod:Copper Pickaxe.png");
+
+        let base_image = packs.image_by_name("Copper Pickaxe.png").unwrap();
+        let modded_image = packs.image_by_name("mod:Copper Pickaxe.png").unwrap();
+        assert_ne!(base_image.data.as_slice(), &[1, 2, 3]);
+        assert_eq!(modded_image.data.as_slice(), &[1, 2, 3]);
+
+        // The mod has no Wood icon of its own, so the base game's is used.
+        let murasama = packs.item_by_name("Murasama").unwrap();
+        assert_eq!(packs.image_name(murasama).unwrap(), "Wood.png");
+        assert!(packs.image_by_name("Wood.png").is_some());
+    }
+}
@@ -0,0 +1,158 @@
+//! Hand-written data that the wiki doesn't have, like how the game progresses, kept in
+//! `assets/tdata`.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+
+const MILESTONES: &str = include_str!("../assets/tdata/milestones.toml");
+const ITEMS: &str = include_str!("../assets/tdata/items.toml");
+
+/// A stage of progression through the game, usually marked by beating a boss.
+#[derive(Deserialize)]
+pub struct Milestone {
+    pub name: String,
+    /// The name of the item whose image stands for the milestone.
+    pub icon: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MilestonesFile {
+    milestones: Vec<Milestone>,
+}
+
+/// All milestones, in the order they're reached.
+pub fn milestones() -> &'static [Milestone] {
+    static MILESTONES_FILE: OnceLock<MilestonesFile> = OnceLock::new();
+
+    &MILESTONES_FILE
+        .get_or_init(|| toml::from_str(MILESTONES).expect("invalid milestones.toml"))
+        .milestones
+}
+
+/// What the catalogue in `items.toml` says about an item.
+#[derive(Deserialize)]
+pub struct CatalogueItem {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The milestone the item is usually obtained in.
+    pub milestone: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ItemsFile {
+    items: Vec<CatalogueItem>,
+}
+
+struct Catalogue {
+    items: Vec<CatalogueItem>,
+    by_name: HashMap<String, usize>,
+}
+
+fn catalogue() -> &'static Catalogue {
+    static CATALOGUE: OnceLock<Catalogue> = OnceLock::new();
+
+    CATALOGUE.get_or_init(|| {
+        let items = toml::from_str::<ItemsFile>(ITEMS)
+            .expect("invalid items.toml")
+            .items;
+        let by_name = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.name.clone(), i))
+            .collect();
+
+        Catalogue { items, by_name }
+    })
+}
+
+/// The catalogue entry for an item, by display name.
+pub fn item(name: &str) -> Option<&'static CatalogueItem> {
+    let catalogue = catalogue();
+    catalogue.by_name.get(name).map(|&i| &catalogue.items[i])
+}
+
+/// Every tag used in the catalogue, sorted.
+pub fn tags() -> &'static BTreeSet<String> {
+    static TAGS: OnceLock<BTreeSet<String>> = OnceLock::new();
+
+    TAGS.get_or_init(|| {
+        catalogue()
+            .items
+            .iter()
+            .flat_map(|item| item.tags.iter().cloned())
+            .collect()
+    })
+}
+
+/// Something in the catalogue that doesn't match the data.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    UnknownItem(String),
+    UnknownMilestone { item: String, milestone: String },
+    Duplicate(String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownItem(name) => write!(f, "items.toml: no item named {name:?}"),
+            Self::UnknownMilestone { item, milestone } => {
+                write!(f, "items.toml: {item:?} has unknown milestone {milestone:?}")
+            }
+            Self::Duplicate(name) => write!(f, "items.toml: {name:?} is listed more than once"),
+        }
+    }
+}
+
+/// Checks every item of the catalogue against the items that exist, as told by `is_item`, and
+/// against the milestones in `milestones.toml`.
+pub fn validate(is_item: impl Fn(&str) -> bool) -> Vec<Problem> {
+    let catalogue = catalogue();
+    let mut problems = Vec::new();
+
+    for (i, item) in catalogue.items.iter().enumerate() {
+        if catalogue.by_name.get(&item.name) != Some(&i) {
+            problems.push(Problem::Duplicate(item.name.clone()));
+        }
+
+        if !is_item(&item.name) {
+            problems.push(Problem::UnknownItem(item.name.clone()));
+        }
+
+        if let Some(milestone) = &item.milestone
+            && !milestones().iter().any(|m| &m.name == milestone)
+        {
+            problems.push(Problem::UnknownMilestone {
+                item: item.name.clone(),
+                milestone: milestone.clone(),
+            });
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogue_is_valid() {
+        assert_eq!(validate(|name| wiki_data::ITEMS.by_name(name).is_some()), []);
+    }
+
+    #[test]
+    fn milestone_icons_exist() {
+        for milestone in milestones() {
+            if let Some(icon) = &milestone.icon {
+                assert!(wiki_data::ITEMS.by_name(icon).is_some(), "no item named {icon:?}");
+            }
+        }
+    }
+}
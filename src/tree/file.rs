@@ -0,0 +1,237 @@
+//! The file format trees are shared in, as TOML or JSON. Items are stored by name like in
+//! storage, but opening a file doesn't fail on items the loaded data packs don't have: they're
+//! left out, and reported. Trees are read back from storage the same way.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use super::{Node, Status, Tree, is_one, milestone::Milestone, one};
+use crate::pack;
+
+/// The version of the format that is written. Files with a newer version are refused.
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct TreeFile {
+    version: u32,
+    name: String,
+    #[serde(default)]
+    milestones: Vec<MilestoneFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct MilestoneFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    title: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roots: Vec<NodeFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct NodeFile {
+    item: String,
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    amount: u32,
+    #[serde(default, alias = "obtained", skip_serializing_if = "Status::is_planned")]
+    status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next: Option<Box<NodeFile>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<NodeFile>,
+}
+
+/// Just the version, to check before reading the rest of a file.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// The format of a file, going by its name. TOML unless it ends in `.json`.
+    pub fn of(file_name: &str) -> Self {
+        if file_name.to_lowercase().ends_with(".json") {
+            Self::Json
+        } else {
+            Self::Toml
+        }
+    }
+
+    fn serialize(self, file: &TreeFile) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Toml => toml::to_string_pretty(file)?,
+            Self::Json => serde_json::to_string_pretty(file)?,
+        })
+    }
+
+    fn parse<T: DeserializeOwned>(self, contents: &str) -> anyhow::Result<T> {
+        Ok(match self {
+            Self::Toml => toml::from_str(contents)?,
+            Self::Json => serde_json::from_str(contents)?,
+        })
+    }
+}
+
+impl NodeFile {
+    fn from_node(node: &Node) -> Self {
+        Self {
+            item: pack::get().qualified_name(node.item).into_owned(),
+            amount: node.amount,
+            status: node.status,
+            next: node
+                .next
+                .as_deref()
+                .map(|next| Box::new(Self::from_node(next))),
+            children: node.children.iter().map(Self::from_node).collect(),
+        }
+    }
+
+    /// Resolves the items of this node and everything branching off it. Nodes whose item can't
+    /// be found are left out along with their branches, and their names added to `missing`.
+    pub(crate) fn into_node(self, missing: &mut BTreeSet<String>) -> Option<Node> {
+        let Some(item) = pack::get().item_by_name(&self.item) else {
+            missing.insert(self.item);
+            return None;
+        };
+
+        Some(Node {
+            item,
+            amount: self.amount,
+            status: self.status,
+            next: self
+                .next
+                .and_then(|next| next.into_node(missing))
+                .map(Box::new),
+            children: self
+                .children
+                .into_iter()
+                .filter_map(|child| child.into_node(missing))
+                .collect(),
+        })
+    }
+}
+
+impl MilestoneFile {
+    /// Resolves the items of the roots of this milestone, like [`NodeFile::into_node`].
+    pub(crate) fn into_milestone(self, missing: &mut BTreeSet<String>) -> Milestone {
+        Milestone {
+            image: self.image,
+            title: self.title,
+            roots: self
+                .roots
+                .into_iter()
+                .filter_map(|root| root.into_node(missing))
+                .collect(),
+        }
+    }
+}
+
+pub fn write(tree: &Tree, format: Format) -> anyhow::Result<String> {
+    format.serialize(&TreeFile {
+        version: VERSION,
+        name: tree.name.clone(),
+        milestones: tree
+            .milestones
+            .iter()
+            .map(|milestone| MilestoneFile {
+                image: milestone.image.clone(),
+                title: milestone.title.clone(),
+                roots: milestone.roots.iter().map(NodeFile::from_node).collect(),
+            })
+            .collect(),
+    })
+}
+
+/// Parses a tree file without resolving its items, refusing newer versions of the format.
+fn parse(contents: &str, format: Format) -> anyhow::Result<TreeFile> {
+    let Header { version } = format.parse(contents)?;
+    if version > VERSION {
+        anyhow::bail!(
+            "the file is from a newer version of terratree (format version {version}, this \
+             version reads up to {VERSION})"
+        );
+    }
+
+    format.parse(contents)
+}
+
+/// Reads a tree file, returning the tree along with the names of items that were left out.
+pub fn read(contents: &str, format: Format) -> anyhow::Result<(Tree, BTreeSet<String>)> {
+    let file = parse(contents, format)?;
+    let mut missing = BTreeSet::new();
+
+    let milestones = file
+        .milestones
+        .into_iter()
+        .map(|milestone| milestone.into_milestone(&mut missing))
+        .collect();
+
+    Ok((Tree::from_milestones(file.name, milestones), missing))
+}
+
+/// Rewrites a tree file in another format, as the current version. Items are kept by name
+/// without looking them up, so this doesn't need the data packs and loses nothing.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn convert(contents: &str, from: Format, to: Format) -> anyhow::Result<String> {
+    let mut file = parse(contents, from)?;
+    file.version = VERSION;
+    to.serialize(&file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_items() {
+        pack::load_embedded();
+
+        let contents = r#"
+            version = 1
+            name = "Shared"
+
+            [[milestones]]
+            title = "Start"
+
+            [[milestones.roots]]
+            item = "Copper Pickaxe"
+            status = "obtained"
+
+            [[milestones.roots.children]]
+            item = "Not An Item"
+
+            [[milestones.roots.children]]
+            item = "Wood"
+            amount = 3
+        "#;
+
+        let (tree, missing) = read(contents, Format::Toml).unwrap();
+        assert_eq!(missing, BTreeSet::from(["Not An Item".to_owned()]));
+
+        let root = &tree.milestones[0].roots[0];
+        assert_eq!(root.status, Status::Obtained);
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].amount, 3);
+
+        for format in [Format::Toml, Format::Json] {
+            let written = write(&tree, format).unwrap();
+            let (read_back, missing) = read(&written, format).unwrap();
+
+            assert!(missing.is_empty());
+            assert_eq!(write(&read_back, format).unwrap(), written);
+        }
+    }
+
+    #[test]
+    fn newer_version() {
+        assert!(read("version = 2\nname = \"Future\"", Format::Toml).is_err());
+        assert!(read(r#"{ "version": 1, "name": "Empty" }"#, Format::Json).is_ok());
+    }
+}
@@ -0,0 +1,590 @@
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Node, Status, Tree, milestone::Milestone};
+
+/// How many edits are kept to undo.
+const MAX_EDITS: usize = 100;
+
+/// How many nodes all kept edits may hold between them, so that a few edits of huge subtrees
+/// can't take up unbounded memory and storage.
+const MAX_NODES: usize = 20_000;
+
+/// Where a node is in a tree: a root of a milestone, followed by the way down from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Path {
+    pub milestone: usize,
+    pub root: usize,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Step {
+    Child(usize),
+    Next,
+}
+
+impl Path {
+    pub fn root(milestone: usize, root: usize) -> Self {
+        Self { milestone, root, steps: Vec::new() }
+    }
+
+    pub fn child(&self, index: usize) -> Self {
+        self.with(Step::Child(index))
+    }
+
+    pub fn next(&self) -> Self {
+        self.with(Step::Next)
+    }
+
+    fn with(&self, step: Step) -> Self {
+        let mut path = self.clone();
+        path.steps.push(step);
+        path
+    }
+}
+
+/// A single change to a tree. Every operation carries what it needs to be undone.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Op {
+    Insert {
+        at: Path,
+        node: Node,
+    },
+    Remove {
+        at: Path,
+        node: Node,
+    },
+    SetStatus {
+        at: Path,
+        old: Status,
+        new: Status,
+    },
+    /// Replaces the children of a node, like expanding its ingredients does.
+    SetChildren {
+        at: Path,
+        old: Vec<Node>,
+        new: Vec<Node>,
+    },
+    InsertMilestone {
+        index: usize,
+        milestone: Milestone,
+    },
+    RemoveMilestone {
+        index: usize,
+        milestone: Milestone,
+    },
+}
+
+/// Everything one user action changed, undone and redone as a whole.
+pub type Edit = Vec<Op>;
+
+impl Op {
+    fn inverse(self) -> Self {
+        match self {
+            Self::Insert { at, node } => Self::Remove { at, node },
+            Self::Remove { at, node } => Self::Insert { at, node },
+            Self::SetStatus { at, old, new } => Self::SetStatus { at, old: new, new: old },
+            Self::SetChildren { at, old, new } => Self::SetChildren { at, old: new, new: old },
+            Self::InsertMilestone { index, milestone } => {
+                Self::RemoveMilestone { index, milestone }
+            }
+            Self::RemoveMilestone { index, milestone } => {
+                Self::InsertMilestone { index, milestone }
+            }
+        }
+    }
+
+    /// Applies the operation, returning whether it could be. Operations that don't fit the tree
+    /// anymore, like removing a node that isn't there, leave it untouched.
+    fn apply(&self, tree: &mut Tree) -> bool {
+        match self {
+            Self::Insert { at, node } => insert(tree, at, node.clone()),
+            Self::Remove { at, .. } => remove(tree, at).is_some(),
+            Self::SetStatus { at, new, .. } => {
+                node_mut(tree, at).map(|n| n.status = *new).is_some()
+            }
+            Self::SetChildren { at, new, .. } => node_mut(tree, at)
+                .map(|n| n.children = new.clone())
+                .is_some(),
+            Self::InsertMilestone { index, milestone } => {
+                let fits = *index <= tree.milestones.len();
+                if fits {
+                    tree.milestones.insert(*index, milestone.clone());
+                }
+                fits
+            }
+            Self::RemoveMilestone { index, .. } => {
+                let fits = *index < tree.milestones.len();
+                if fits {
+                    tree.milestones.remove(*index);
+                }
+                fits
+            }
+        }
+    }
+
+    /// Roughly how much memory the operation holds, in nodes.
+    fn size(&self) -> usize {
+        match self {
+            Self::Insert { node, .. } | Self::Remove { node, .. } => node.size(),
+            Self::SetStatus { .. } => 1,
+            Self::SetChildren { old, new, .. } => old.iter().chain(new).map(Node::size).sum(),
+            Self::InsertMilestone { milestone, .. } | Self::RemoveMilestone { milestone, .. } => {
+                milestone.roots.iter().map(Node::size).sum::<usize>() + 1
+            }
+        }
+    }
+}
+
+impl Node {
+    /// The number of nodes in this node, its children and the nodes after it.
+    fn size(&self) -> usize {
+        1 + self.children.iter().map(Node::size).sum::<usize>()
+            + self.next.as_ref().map_or(0, |next| next.size())
+    }
+}
+
+fn node_mut<'a>(tree: &'a mut Tree, path: &Path) -> Option<&'a mut Node> {
+    let mut node = tree
+        .milestones
+        .get_mut(path.milestone)?
+        .roots
+        .get_mut(path.root)?;
+
+    for step in &path.steps {
+        node = match step {
+            Step::Child(index) => node.children.get_mut(*index)?,
+            Step::Next => node.next.as_deref_mut()?,
+        };
+    }
+
+    Some(node)
+}
+
+/// The parent of the node at `path`, along with the last step to it, or `None` for roots.
+fn parent_mut<'a>(tree: &'a mut Tree, path: &Path) -> Option<(&'a mut Node, Step)> {
+    let (last, steps) = path.steps.split_last()?;
+    let parent = Path {
+        steps: steps.to_vec(),
+        ..path.clone()
+    };
+    Some((node_mut(tree, &parent)?, *last))
+}
+
+fn insert(tree: &mut Tree, at: &Path, node: Node) -> bool {
+    if at.steps.is_empty() {
+        return match tree.milestones.get_mut(at.milestone) {
+            Some(milestone) if at.root <= milestone.roots.len() => {
+                milestone.roots.insert(at.root, node);
+                true
+            }
+            _ => false,
+        };
+    }
+
+    match parent_mut(tree, at) {
+        Some((parent, Step::Child(index))) if index <= parent.children.len() => {
+            parent.children.insert(index, node);
+            true
+        }
+        Some((parent, Step::Next)) if parent.next.is_none() => {
+            parent.next = Some(Box::new(node));
+            true
+        }
+        _ => false,
+    }
+}
+
+fn remove(tree: &mut Tree, at: &Path) -> Option<Node> {
+    if at.steps.is_empty() {
+        let roots = &mut tree.milestones.get_mut(at.milestone)?.roots;
+        return (at.root < roots.len()).then(|| roots.remove(at.root));
+    }
+
+    match parent_mut(tree, at)? {
+        (parent, Step::Child(index)) if index < parent.children.len() => {
+            Some(parent.children.remove(index))
+        }
+        (parent, Step::Next) => parent.next.take().map(|next| *next),
+        _ => None,
+    }
+}
+
+/// Edits that can be undone and redone. Kept with the tree, so it survives restarts.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    #[serde(skip)]
+    revision: Revision,
+}
+
+/// Identifies the nodes of a tree as they are. Every edit, undo and redo gives the tree a new
+/// one, so two trees only have the same revision if one is an unedited copy of the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Revision(u64);
+
+impl Default for Revision {
+    /// A revision no tree had before.
+    fn default() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl History {
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn push_undo(&mut self, edit: Edit) {
+        self.undo.push_back(edit);
+
+        let mut size = self.undo.iter().flatten().map(Op::size).sum::<usize>();
+        while self.undo.len() > MAX_EDITS || (size > MAX_NODES && self.undo.len() > 1) {
+            if let Some(dropped) = self.undo.pop_front() {
+                size -= dropped.iter().map(Op::size).sum::<usize>();
+            }
+        }
+    }
+}
+
+impl Tree {
+    /// Applies an edit and records it to be undone. Clears what could be redone.
+    pub fn apply(&mut self, edit: Edit) {
+        if let Some(applied) = self.apply_ops(edit) {
+            self.history.redo.clear();
+            self.history.push_undo(applied);
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(edit) = self.history.undo.pop_back()
+            && let Some(undone) = self.apply_ops(invert(edit))
+        {
+            self.history.redo.push(invert(undone));
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(edit) = self.history.redo.pop()
+            && let Some(redone) = self.apply_ops(edit)
+        {
+            self.history.push_undo(redone);
+        }
+    }
+
+    /// Applies the operations of an edit in order, skipping the ones that don't fit the tree.
+    /// Returns the ones that were applied, if any, and moves the tree to a new revision if so.
+    fn apply_ops(&mut self, edit: Edit) -> Option<Edit> {
+        let applied = edit
+            .into_iter()
+            .filter(|op| {
+                let applied = op.apply(self);
+                if !applied {
+                    log::warn!("skipping tree edit that doesn't fit the tree anymore");
+                }
+                applied
+            })
+            .collect::<Edit>();
+
+        if applied.is_empty() {
+            return None;
+        }
+
+        self.history.revision = Revision::default();
+        Some(applied)
+    }
+}
+
+/// The edit that undoes `edit`.
+fn invert(edit: Edit) -> Edit {
+    edit.into_iter().rev().map(Op::inverse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack;
+
+    fn node(name: &str) -> Node {
+        pack::load_embedded();
+        Node::from_name(name).unwrap()
+    }
+
+    /// A tree with a milestone holding Copper Pickaxe, made of Copper Bar and Wood, then Torch.
+    fn tree() -> Tree {
+        let mut root = node("Copper Pickaxe");
+        root.children = vec![node("Copper Bar"), node("Wood")];
+        root.next = Some(Box::new(node("Torch")));
+
+        Tree::from_milestones(
+            "Plan".to_owned(),
+            vec![Milestone {
+                image: None,
+                title: "Start".to_owned(),
+                roots: vec![root],
+            }],
+        )
+    }
+
+    /// The tree as text, like "Copper Pickaxe(Copper Bar, Wood) > Torch" for each milestone.
+    fn outline(tree: &Tree) -> Vec<String> {
+        fn line(node: &Node) -> String {
+            let mut out = node.item.name.to_string();
+            if !node.children.is_empty() {
+                let children = node.children.iter().map(line).collect::<Vec<_>>();
+                out += &format!("({})", children.join(", "));
+            }
+            if let Some(next) = &node.next {
+                out += &format!(" > {}", line(next));
+            }
+            out
+        }
+
+        tree.milestones
+            .iter()
+            .map(|m| m.roots.iter().map(line).collect::<Vec<_>>().join("; "))
+            .collect()
+    }
+
+    fn name_at(tree: &mut Tree, path: &Path) -> Option<String> {
+        node_mut(tree, path).map(|node| node.item.name.to_string())
+    }
+
+    #[test]
+    fn paths() {
+        let mut tree = tree();
+        let root = Path::root(0, 0);
+
+        assert_eq!(name_at(&mut tree, &root).as_deref(), Some("Copper Pickaxe"));
+        assert_eq!(name_at(&mut tree, &root.child(1)).as_deref(), Some("Wood"));
+        assert_eq!(name_at(&mut tree, &root.next()).as_deref(), Some("Torch"));
+        assert_eq!(name_at(&mut tree, &root.child(2)), None);
+        assert_eq!(name_at(&mut tree, &root.next().next()), None);
+        assert_eq!(name_at(&mut tree, &Path::root(0, 1)), None);
+        assert_eq!(name_at(&mut tree, &Path::root(1, 0)), None);
+
+        let (parent, step) = parent_mut(&mut tree, &root.child(1)).unwrap();
+        assert_eq!(parent.item.name, "Copper Pickaxe");
+        assert_eq!(step, Step::Child(1));
+        assert!(parent_mut(&mut tree, &root).is_none());
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut tree = tree();
+        let root = Path::root(0, 0);
+
+        assert!(insert(&mut tree, &Path::root(0, 1), node("Gel")));
+        assert!(insert(&mut tree, &root.child(2), node("Gel")));
+        assert!(insert(&mut tree, &root.child(1).next(), node("Gel")));
+        assert_eq!(outline(&tree), ["Copper Pickaxe(Copper Bar, Wood > Gel, Gel) > Torch; Gel"]);
+
+        // Past the end, or onto a node that already has one after it.
+        assert!(!insert(&mut tree, &Path::root(0, 3), node("Gel")));
+        assert!(!insert(&mut tree, &Path::root(1, 0), node("Gel")));
+        assert!(!insert(&mut tree, &root.child(4), node("Gel")));
+        assert!(!insert(&mut tree, &root.next(), node("Gel")));
+        assert!(!insert(&mut tree, &root.child(5).child(0), node("Gel")));
+
+        assert_eq!(remove(&mut tree, &root.child(1).next()).unwrap().item.name, "Gel");
+        assert_eq!(remove(&mut tree, &root.child(2)).unwrap().item.name, "Gel");
+        assert_eq!(remove(&mut tree, &Path::root(0, 1)).unwrap().item.name, "Gel");
+        assert_eq!(remove(&mut tree, &root.next()).unwrap().item.name, "Torch");
+        assert_eq!(outline(&tree), ["Copper Pickaxe(Copper Bar, Wood)"]);
+
+        assert!(remove(&mut tree, &root.next()).is_none());
+        assert!(remove(&mut tree, &root.child(2)).is_none());
+        assert!(remove(&mut tree, &Path::root(0, 1)).is_none());
+        assert!(remove(&mut tree, &Path::root(1, 0)).is_none());
+    }
+
+    #[test]
+    fn inverse() {
+        let at = Path::root(0, 0).child(1);
+        let milestone = Milestone {
+            image: None,
+            title: "Later".to_owned(),
+            roots: Vec::new(),
+        };
+
+        let op = Op::Insert { at: at.clone(), node: node("Gel") }.inverse();
+        assert!(matches!(&op, Op::Remove { at: a, node } if *a == at && node.item.name == "Gel"));
+        assert!(matches!(op.inverse(), Op::Insert { .. }));
+
+        let op = Op::SetStatus {
+            at: at.clone(),
+            old: Status::Planned,
+            new: Status::Obtained,
+        };
+        assert!(matches!(
+            op.inverse(),
+            Op::SetStatus {
+                old: Status::Obtained,
+                new: Status::Planned,
+                ..
+            }
+        ));
+
+        let op = Op::SetChildren {
+            at,
+            old: Vec::new(),
+            new: vec![node("Gel")],
+        }
+        .inverse();
+        assert!(matches!(op, Op::SetChildren { old, new, .. } if old.len() == 1 && new.is_empty()));
+
+        let op = Op::InsertMilestone { index: 1, milestone }.inverse();
+        assert!(matches!(&op, Op::RemoveMilestone { index: 1, .. }));
+        assert!(matches!(op.inverse(), Op::InsertMilestone { index: 1, .. }));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut tree = tree();
+        let root = Path::root(0, 0);
+        let start = outline(&tree);
+
+        tree.apply(vec![
+            Op::Remove { at: root.next(), node: node("Torch") },
+            Op::Insert { at: root.child(0), node: node("Gel") },
+        ]);
+        tree.apply(vec![Op::SetChildren {
+            at: root.child(0),
+            old: Vec::new(),
+            new: vec![node("Wood")],
+        }]);
+        tree.apply(vec![Op::InsertMilestone {
+            index: 1,
+            milestone: Milestone {
+                image: None,
+                title: "Later".to_owned(),
+                roots: vec![node("Torch")],
+            },
+        }]);
+        let end = outline(&tree);
+        assert_eq!(end, ["Copper Pickaxe(Gel(Wood), Copper Bar, Wood)", "Torch"]);
+
+        for _ in 0..3 {
+            tree.undo();
+        }
+        assert_eq!(outline(&tree), start);
+        assert!(!tree.history.can_undo());
+
+        for _ in 0..3 {
+            tree.redo();
+        }
+        assert_eq!(outline(&tree), end);
+        assert!(!tree.history.can_redo());
+
+        tree.undo();
+        tree.apply(vec![Op::SetStatus {
+            at: root.clone(),
+            old: Status::Planned,
+            new: Status::Obtained,
+        }]);
+        assert!(!tree.history.can_redo());
+
+        tree.undo();
+        assert_eq!(tree.milestones[0].roots[0].status, Status::Planned);
+    }
+
+    #[test]
+    fn revisions() {
+        let mut tree = tree();
+        let start = tree.history.revision();
+        assert_eq!(tree.clone().history.revision(), start);
+        assert_ne!(Tree::new("Other".to_owned(), None).history.revision(), start);
+
+        tree.apply(vec![Op::Insert {
+            at: Path::root(0, 1),
+            node: node("Gel"),
+        }]);
+        let applied = tree.history.revision();
+        assert_ne!(applied, start);
+
+        tree.undo();
+        assert_ne!(tree.history.revision(), applied);
+        assert_ne!(tree.history.revision(), start);
+
+        // Edits that change nothing keep the revision.
+        let undone = tree.history.revision();
+        tree.apply(vec![Op::Remove {
+            at: Path::root(0, 5),
+            node: node("Gel"),
+        }]);
+        tree.undo();
+        assert_eq!(tree.history.revision(), undone);
+    }
+
+    #[test]
+    fn keeps_edits_within_limits() {
+        let mut history = History::default();
+        let set_status = Op::SetStatus {
+            at: Path::root(0, 0),
+            old: Status::Planned,
+            new: Status::Obtained,
+        };
+
+        for _ in 0..MAX_EDITS + 10 {
+            history.push_undo(vec![set_status.clone()]);
+        }
+        assert_eq!(history.undo.len(), MAX_EDITS);
+
+        let mut big = node("Wood");
+        big.children = vec![node("Gel"); MAX_NODES / 2];
+        let insert_big = Op::Insert { at: Path::root(0, 0), node: big };
+
+        // Dropped oldest first, until what's left is within the limit.
+        history.push_undo(vec![insert_big.clone()]);
+        assert_eq!(history.undo.len(), MAX_EDITS);
+        history.push_undo(vec![insert_big.clone()]);
+        assert!(history.undo.iter().flatten().map(Op::size).sum::<usize>() <= MAX_NODES);
+        assert_eq!(history.undo.len(), 1);
+
+        // An edit bigger than the limit on its own is still kept.
+        history.push_undo(vec![insert_big.clone(), insert_big]);
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].len(), 2);
+    }
+
+    #[test]
+    fn skips_ops_that_dont_fit() {
+        let mut tree = tree();
+        let root = Path::root(0, 0);
+
+        tree.apply(vec![
+            Op::Remove { at: root.child(5), node: node("Gel") },
+            Op::Insert { at: root.child(0), node: node("Gel") },
+        ]);
+        assert_eq!(tree.history.undo.back().unwrap().len(), 1);
+
+        tree.apply(vec![Op::RemoveMilestone {
+            index: 3,
+            milestone: tree.milestones[0].clone(),
+        }]);
+        assert_eq!(tree.history.undo.len(), 1);
+
+        // The node the undo would remove from is gone, so there's nothing to redo either.
+        let removed = remove(&mut tree, &root).unwrap();
+        tree.undo();
+        assert!(!tree.history.can_undo());
+        assert!(!tree.history.can_redo());
+        assert_eq!(outline(&tree), [""]);
+        assert_eq!(removed.children.len(), 3);
+    }
+}
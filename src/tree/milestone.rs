@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use wiki_data::ArchivedItem;
+
+use super::Node;
+use crate::{pack, tdata};
+
+/// A section of a tree for one stage of progression, holding what to get during it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    /// The wiki image shown next to the title.
+    pub image: Option<String>,
+    pub title: String,
+    pub roots: Vec<Node>,
+}
+
+impl Milestone {
+    /// Empty sections for the milestones in `milestones.toml`.
+    pub fn defaults() -> Vec<Self> {
+        tdata::milestones()
+            .iter()
+            .map(|milestone| Self {
+                image: milestone
+                    .icon
+                    .as_deref()
+                    .and_then(|name| pack::get().item_by_name(name))
+                    .and_then(|item| pack::get().image_name(item)),
+                title: milestone.name.clone(),
+                roots: Vec::new(),
+            })
+            .collect()
+    }
+
+    pub fn from_item(item: &'static ArchivedItem) -> Self {
+        Self {
+            image: pack::get().image_name(item),
+            title: item.name.to_string(),
+            roots: Vec::new(),
+        }
+    }
+}
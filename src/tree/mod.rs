@@ -0,0 +1,131 @@
+//! The tree model: nodes, milestones and trees, with their undo history, the tree file format
+//! and rendering to images. The app draws and edits trees, the command line works on tree files.
+
+use serde::{Deserialize, Serialize};
+use wiki_data::ArchivedItem;
+
+use crate::{pack, tdata};
+
+pub mod file;
+mod history;
+pub mod milestone;
+mod node_by_name;
+pub mod render;
+mod status;
+
+pub use history::{Edit, History, Op, Path, Revision};
+pub use status::Status;
+
+use milestone::Milestone;
+
+// How trees are laid out, by the canvas and in rendered images alike.
+pub const ICON_SIZE: f32 = 48.;
+pub const MILESTONE_ICON_SIZE: f32 = 32.;
+/// How far the roots of a milestone are indented from its title.
+pub const INDENT: f32 = 18.;
+pub const SPACING: f32 = 3.;
+pub const MILESTONE_SPACING: f32 = 8.;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Node {
+    #[serde(with = "node_by_name")]
+    pub item: &'static ArchivedItem,
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub amount: u32,
+    #[serde(default, alias = "obtained", skip_serializing_if = "Status::is_planned")]
+    pub status: Status,
+    pub next: Option<Box<Node>>,
+    pub children: Vec<Node>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn is_one(amount: &u32) -> bool {
+    *amount == 1
+}
+
+impl Node {
+    pub fn from_item(item: &'static ArchivedItem) -> Self {
+        Self {
+            item,
+            amount: 1,
+            status: Status::Planned,
+            next: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        pack::get().item_by_name(name).map(Self::from_item)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Tree {
+    pub name: String,
+    pub milestones: Vec<Milestone>,
+    pub history: History,
+}
+
+impl Tree {
+    /// A tree with the default milestones, and `root` in the first one.
+    pub fn new(name: String, root: Option<Node>) -> Self {
+        let mut milestones = Milestone::defaults();
+
+        if let Some(root) = root {
+            match milestones.first_mut() {
+                Some(first) => first.roots.insert(0, root),
+                None => milestones.push(Milestone {
+                    image: None,
+                    title: "Start".to_owned(),
+                    roots: vec![root],
+                }),
+            }
+        }
+
+        Self::from_milestones(name, milestones)
+    }
+
+    pub fn from_milestones(name: String, milestones: Vec<Milestone>) -> Self {
+        Self {
+            name,
+            milestones,
+            history: History::default(),
+        }
+    }
+
+    /// Adds an item as a new root, in the milestone the catalogue places it in. Items the
+    /// catalogue doesn't place, or whose milestone isn't in this tree, go into the first one.
+    pub fn add_item(&mut self, item: &'static ArchivedItem) {
+        let node = Node::from_item(item);
+
+        let milestone = tdata::item(&item.name)
+            .and_then(|entry| entry.milestone.as_deref())
+            .and_then(|title| self.milestones.iter().position(|m| m.title == title))
+            .unwrap_or(0);
+
+        let op = match self.milestones.get(milestone) {
+            Some(m) => Op::Insert {
+                at: Path::root(milestone, m.roots.len()),
+                node,
+            },
+            None => Op::InsertMilestone {
+                index: self.milestones.len(),
+                milestone: Milestone {
+                    image: None,
+                    title: "Start".to_owned(),
+                    roots: vec![node],
+                },
+            },
+        };
+
+        self.apply(vec![op]);
+    }
+
+    /// The roots of all milestones, in order.
+    pub fn roots(&self) -> impl Iterator<Item = &Node> {
+        self.milestones.iter().flat_map(|m| &m.roots)
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Deserializer, Serializer};
+use wiki_data::ArchivedItem;
+
+use crate::pack;
+
+pub fn serialize<S>(item: &&'static ArchivedItem, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&pack::get().qualified_name(item))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<&'static ArchivedItem, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+
+    pack::get()
+        .item_by_name(&name)
+        .ok_or_else(|| serde::de::Error::custom(format!("Item not found: {name}")))
+}
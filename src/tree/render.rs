@@ -0,0 +1,568 @@
+//! Renders a tree to a standalone SVG or PNG, laid out the way the canvas draws it. Both are
+//! drawn on the CPU, so they work without a window.
+
+use std::{collections::HashMap, fmt::Write as _, io::Cursor};
+
+use ab_glyph::{Font as _, FontRef, PxScale, ScaleFont as _};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use egui::{Color32, Pos2, Rect, pos2, vec2};
+use image::{RgbaImage, imageops};
+
+use super::{
+    ICON_SIZE, INDENT, MILESTONE_ICON_SIZE, MILESTONE_SPACING, Node, SPACING, Status, Tree,
+};
+use crate::pack;
+
+const MARGIN: f32 = 16.;
+
+const HEADING_SIZE: f32 = 18.;
+const BODY_SIZE: f32 = 12.5;
+
+const BACKGROUND: Color32 = Color32::from_gray(248);
+const TEXT: Color32 = Color32::from_gray(20);
+const LINE: Color32 = Color32::from_gray(150);
+const MAIN_LINE: Color32 = Color32::from_gray(60);
+
+/// The most pixels a PNG export may have, so a huge tree can't allocate without bound. That's
+/// about 256 MB of RGBA, well past any tree drawn by hand.
+const MAX_PIXELS: u64 = 64 << 20;
+
+enum Shape {
+    /// A wiki image, scaled to fit `rect`, with `opacity` standing in for the icon tint.
+    Image {
+        name: String,
+        rect: Rect,
+        opacity: f32,
+    },
+    Line {
+        from: Pos2,
+        to: Pos2,
+        width: f32,
+        color: Color32,
+    },
+    Circle {
+        center: Pos2,
+        radius: f32,
+        color: Color32,
+    },
+    Rect {
+        rect: Rect,
+        color: Color32,
+    },
+    /// Text starting at `pos` on its baseline.
+    Text {
+        pos: Pos2,
+        text: String,
+        size: f32,
+        color: Color32,
+    },
+}
+
+/// Renders a tree as an SVG if `file_name` ends in `.svg`, or as a PNG otherwise.
+pub fn export(tree: &Tree, file_name: &str) -> anyhow::Result<Vec<u8>> {
+    let drawing = Drawing::of(tree);
+
+    if file_name.to_lowercase().ends_with(".svg") {
+        Ok(drawing.to_svg().into_bytes())
+    } else {
+        drawing.to_png()
+    }
+}
+
+/// A tree laid out into shapes, ready to be written as an image.
+pub struct Drawing {
+    size: egui::Vec2,
+    shapes: Vec<Shape>,
+    font: FontRef<'static>,
+}
+
+impl Drawing {
+    pub fn of(tree: &Tree) -> Self {
+        let mut drawing = Self {
+            size: egui::Vec2::ZERO,
+            shapes: Vec::new(),
+            font: FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+                .expect("egui's default font is valid"),
+        };
+
+        let mut y = MARGIN;
+
+        for milestone in &tree.milestones {
+            let mut x = MARGIN;
+
+            if let Some(image) = &milestone.image {
+                drawing.shapes.push(Shape::Image {
+                    name: image.clone(),
+                    rect: Rect::from_min_size(
+                        pos2(x, y),
+                        vec2(MILESTONE_ICON_SIZE, MILESTONE_ICON_SIZE),
+                    ),
+                    opacity: 1.,
+                });
+                x += MILESTONE_ICON_SIZE + SPACING * 2.;
+            }
+
+            drawing.text(
+                pos2(x, y + (MILESTONE_ICON_SIZE + HEADING_SIZE) / 2. - 3.),
+                &milestone.title,
+                HEADING_SIZE,
+                TEXT,
+            );
+            y += MILESTONE_ICON_SIZE + SPACING;
+
+            for root in &milestone.roots {
+                y = drawing.node(root, pos2(MARGIN + INDENT, y)).1;
+            }
+
+            y += MILESTONE_SPACING;
+        }
+
+        drawing.size = vec2(drawing.size.x + MARGIN, y.max(MARGIN * 2.) + MARGIN).ceil();
+        drawing
+    }
+
+    /// Lays out a node at `pos` like [`Node::ui`] does: children to the right below it, each
+    /// connected to a line down from it, and the node after it below them. Returns the rect of
+    /// its icon and where the next thing goes below it.
+    fn node(&mut self, node: &Node, pos: Pos2) -> (Rect, f32) {
+        let icon = Rect::from_min_size(pos, vec2(ICON_SIZE, ICON_SIZE));
+
+        if let Some(name) = pack::get().image_name(node.item) {
+            self.shapes.push(Shape::Image {
+                name,
+                rect: icon,
+                opacity: f32::from(node.status.tint().r()) / 255.,
+            });
+        }
+        self.status(node.status, icon);
+
+        if !node.children.is_empty()
+            && let Some(progress) = node.progress()
+        {
+            let bar =
+                Rect::from_min_max(pos2(icon.left(), icon.bottom() - 3.), icon.right_bottom());
+            self.shapes.push(Shape::Rect {
+                rect: bar,
+                color: Color32::from_black_alpha(160),
+            });
+            self.shapes.push(Shape::Rect {
+                rect: Rect::from_min_max(
+                    bar.min,
+                    pos2(bar.left() + bar.width() * progress, bar.bottom()),
+                ),
+                color: Color32::from_rgb(0x40, 0xb0, 0x40),
+            });
+        }
+
+        if node.amount > 1 {
+            let amount = node.amount.to_string();
+            let width = self.text_width(&amount, BODY_SIZE);
+            self.text(icon.right_bottom() - vec2(width, 2.), &amount, BODY_SIZE, TEXT);
+        }
+
+        let main_line_x = icon.center().x;
+        let mut y = icon.bottom() + SPACING;
+        let mut last_line_y = None;
+
+        for child in &node.children {
+            let (child_icon, bottom) = self.node(child, pos2(icon.left() + ICON_SIZE, y));
+
+            self.line(child_icon.left_center(), pos2(main_line_x, child_icon.center().y), LINE);
+            last_line_y = Some(child_icon.center().y);
+            y = bottom;
+        }
+
+        if let Some(next) = &node.next {
+            self.line(icon.center_bottom(), pos2(main_line_x, y), MAIN_LINE);
+            y = self.node(next, pos2(icon.left(), y)).1;
+        } else if let Some(line_y) = last_line_y {
+            self.line(icon.center_bottom(), pos2(main_line_x, line_y), LINE);
+        }
+
+        self.size.x = self.size.x.max(icon.right());
+
+        (icon, y)
+    }
+
+    /// The badge for a status in the top left corner of an icon, like
+    /// [`Status::paint_overlay`], with the symbols drawn as lines.
+    fn status(&mut self, status: Status, icon: Rect) {
+        let center = icon.left_top() + vec2(8., 8.);
+        let white = Color32::WHITE;
+
+        let Some(color) = status.color() else {
+            return;
+        };
+
+        if status == Status::Skipped {
+            self.line(icon.left_bottom(), icon.right_top(), Color32::from_gray(140));
+        }
+
+        self.shapes
+            .push(Shape::Circle { center, radius: 7., color });
+
+        match status {
+            Status::InProgress => {
+                for dx in [-3., 0., 3.] {
+                    self.shapes.push(Shape::Circle {
+                        center: center + vec2(dx, 0.),
+                        radius: 1.,
+                        color: white,
+                    });
+                }
+            }
+            Status::Obtained => {
+                self.line(center + vec2(-3.5, 0.), center + vec2(-1., 2.5), white);
+                self.line(center + vec2(-1., 2.5), center + vec2(3.5, -2.5), white);
+            }
+            Status::Skipped => {
+                self.line(center + vec2(-3., -3.), center + vec2(3., 3.), white);
+                self.line(center + vec2(-3., 3.), center + vec2(3., -3.), white);
+            }
+            Status::Planned => {}
+        }
+    }
+
+    fn line(&mut self, from: Pos2, to: Pos2, color: Color32) {
+        self.shapes
+            .push(Shape::Line { from, to, width: 1.5, color });
+    }
+
+    fn text(&mut self, pos: Pos2, text: &str, size: f32, color: Color32) {
+        self.size.x = self.size.x.max(pos.x + self.text_width(text, size));
+        self.shapes.push(Shape::Text {
+            pos,
+            text: text.to_owned(),
+            size,
+            color,
+        });
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let mut width = 0.;
+        let mut previous = None;
+
+        for c in text.chars() {
+            let glyph = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, glyph);
+            }
+            width += font.h_advance(glyph);
+            previous = Some(glyph);
+        }
+
+        width
+    }
+
+    /// Writes the drawing as an SVG, with the icons embedded.
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let (width, height) = (self.size.x, self.size.y);
+
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )
+        .ok();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, hex(BACKGROUND)).ok();
+
+        // Every icon is embedded once, and placed with `<use>` wherever it's drawn.
+        let mut symbols = HashMap::new();
+        svg.push_str("<defs>\n");
+        for shape in &self.shapes {
+            if let Shape::Image { name, .. } = shape
+                && !symbols.contains_key(name)
+                && let Some(image) = pack::get().image_by_name(name)
+            {
+                let id = format!("icon{}", symbols.len());
+                let (w, h) = (image.width.to_native(), image.height.to_native());
+                let mime = image::guess_format(&image.data)
+                    .map(|format| format.to_mime_type())
+                    .unwrap_or("image/png");
+
+                writeln!(
+                    svg,
+                    r#"<symbol id="{id}" viewBox="0 0 {w} {h}"><image width="{w}" height="{h}" style="image-rendering:pixelated" href="data:{mime};base64,{}"/></symbol>"#,
+                    STANDARD.encode(image.data.as_slice()),
+                )
+                .ok();
+                symbols.insert(name.clone(), id);
+            }
+        }
+        svg.push_str("</defs>\n");
+
+        for shape in &self.shapes {
+            match shape {
+                Shape::Image { name, rect, opacity } => {
+                    if let Some(id) = symbols.get(name) {
+                        writeln!(
+                            svg,
+                            r##"<use href="#{id}" x="{}" y="{}" width="{}" height="{}" opacity="{opacity:.2}"/>"##,
+                            rect.left(),
+                            rect.top(),
+                            rect.width(),
+                            rect.height(),
+                        )
+                        .ok();
+                    }
+                }
+                Shape::Line { from, to, width, color } => {
+                    writeln!(
+                        svg,
+                        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{width}" stroke-linecap="round"/>"#,
+                        from.x,
+                        from.y,
+                        to.x,
+                        to.y,
+                        hex(*color),
+                    )
+                    .ok();
+                }
+                Shape::Circle { center, radius, color } => {
+                    writeln!(
+                        svg,
+                        r#"<circle cx="{}" cy="{}" r="{radius}" fill="{}"/>"#,
+                        center.x,
+                        center.y,
+                        hex(*color),
+                    )
+                    .ok();
+                }
+                Shape::Rect { rect, color } => {
+                    writeln!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{:.2}"/>"#,
+                        rect.left(),
+                        rect.top(),
+                        rect.width(),
+                        rect.height(),
+                        hex(*color),
+                        f32::from(color.a()) / 255.,
+                    )
+                    .ok();
+                }
+                Shape::Text { pos, text, size, color } => {
+                    writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-family="Ubuntu, sans-serif" font-size="{size}" fill="{}">{}</text>"#,
+                        pos.x,
+                        pos.y,
+                        hex(*color),
+                        escape(text),
+                    )
+                    .ok();
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Rasterizes the drawing and encodes it as a PNG. Fails for drawings of more than
+    /// [`MAX_PIXELS`], which only the SVG export can hold.
+    pub fn to_png(&self) -> anyhow::Result<Vec<u8>> {
+        let (width, height) = (self.size.x as u32, self.size.y as u32);
+        if u64::from(width) * u64::from(height) > MAX_PIXELS {
+            anyhow::bail!(
+                "the tree is too large to export as PNG ({width}x{height} pixels); export it as \
+                 SVG instead"
+            );
+        }
+
+        let mut canvas = Canvas {
+            image: RgbaImage::from_pixel(width, height, image::Rgba(BACKGROUND.to_array())),
+        };
+        let mut icons = HashMap::<&str, Option<RgbaImage>>::new();
+
+        for shape in &self.shapes {
+            match shape {
+                Shape::Image { name, rect, opacity } => {
+                    let icon = icons.entry(name).or_insert_with(|| {
+                        let image = pack::get().image_by_name(name)?;
+                        image::load_from_memory(&image.data)
+                            .inspect_err(|e| log::warn!("failed to decode {name}: {e}"))
+                            .ok()
+                            .map(|image| image.into_rgba8())
+                    });
+
+                    if let Some(icon) = icon {
+                        canvas.image(icon, *rect, *opacity);
+                    }
+                }
+                Shape::Line { from, to, width, color } => canvas.line(*from, *to, *width, *color),
+                Shape::Circle { center, radius, color } => {
+                    canvas.fill(
+                        Rect::from_center_size(*center, vec2(radius * 2., radius * 2.)),
+                        *color,
+                        |p| p.distance(*center) <= *radius,
+                    );
+                }
+                Shape::Rect { rect, color } => canvas.fill(*rect, *color, |_| true),
+                Shape::Text { pos, text, size, color } => {
+                    canvas.text(&self.font, *pos, text, *size, *color);
+                }
+            }
+        }
+
+        let mut png = Vec::new();
+        canvas
+            .image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+
+        Ok(png)
+    }
+}
+
+/// A picture being rasterized, with just the drawing operations the shapes need.
+struct Canvas {
+    image: RgbaImage,
+}
+
+impl Canvas {
+    /// Blends `color` over the pixel at `x`, `y`, with `coverage` scaling its alpha.
+    fn blend(&mut self, x: i64, y: i64, color: Color32, coverage: f32) {
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            return;
+        };
+        let Some(pixel) = self.image.get_pixel_mut_checked(x, y) else {
+            return;
+        };
+
+        let alpha = f32::from(color.a()) / 255. * coverage.clamp(0., 1.);
+        let [r, g, b] = [color.r(), color.g(), color.b()];
+
+        for (channel, value) in pixel.0.iter_mut().zip([r, g, b]) {
+            *channel =
+                (f32::from(*channel) * (1. - alpha) + f32::from(value) * alpha).round() as u8;
+        }
+    }
+
+    /// Fills the pixels in `rect` whose center `inside` accepts.
+    fn fill(&mut self, rect: Rect, color: Color32, inside: impl Fn(Pos2) -> bool) {
+        for y in rect.top().floor() as i64..rect.bottom().ceil() as i64 {
+            for x in rect.left().floor() as i64..rect.right().ceil() as i64 {
+                if inside(pos2(x as f32 + 0.5, y as f32 + 0.5)) {
+                    self.blend(x, y, color, 1.);
+                }
+            }
+        }
+    }
+
+    fn line(&mut self, from: Pos2, to: Pos2, width: f32, color: Color32) {
+        let bounds = Rect::from_two_pos(from, to).expand(width);
+        let along = to - from;
+        let length_sq = along.length_sq().max(f32::EPSILON);
+
+        self.fill(bounds, color, |p| {
+            let t = ((p - from).dot(along) / length_sq).clamp(0., 1.);
+            p.distance(from + along * t) <= width / 2.
+        });
+    }
+
+    /// Draws `icon` scaled to fit `rect` keeping its aspect ratio, centered in it.
+    fn image(&mut self, icon: &RgbaImage, rect: Rect, opacity: f32) {
+        let scale = (rect.width() / icon.width() as f32).min(rect.height() / icon.height() as f32);
+        let size = vec2(icon.width() as f32, icon.height() as f32) * scale;
+        let min = rect.center() - size / 2.;
+
+        let scaled = imageops::resize(
+            icon,
+            size.x.round().max(1.) as u32,
+            size.y.round().max(1.) as u32,
+            imageops::FilterType::Nearest,
+        );
+
+        for (x, y, pixel) in scaled.enumerate_pixels() {
+            let [r, g, b, a] = pixel.0;
+            self.blend(
+                min.x.round() as i64 + i64::from(x),
+                min.y.round() as i64 + i64::from(y),
+                Color32::from_rgb(r, g, b),
+                f32::from(a) / 255. * opacity,
+            );
+        }
+    }
+
+    fn text(&mut self, font: &FontRef<'_>, pos: Pos2, text: &str, size: f32, color: Color32) {
+        let scaled = font.as_scaled(PxScale::from(size));
+        let mut x = pos.x;
+        let mut previous = None;
+
+        for c in text.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                x += scaled.kern(previous, id);
+            }
+
+            let glyph = id.with_scale_and_position(size, ab_glyph::point(x, pos.y));
+            if let Some(outline) = font.outline_glyph(glyph) {
+                let bounds = outline.px_bounds();
+                outline.draw(|gx, gy, coverage| {
+                    self.blend(
+                        bounds.min.x as i64 + i64::from(gx),
+                        bounds.min.y as i64 + i64::from(gy),
+                        color,
+                        coverage,
+                    );
+                });
+            }
+
+            x += scaled.h_advance(id);
+            previous = Some(id);
+        }
+    }
+}
+
+fn hex(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::milestone::Milestone;
+
+    #[test]
+    fn export() {
+        pack::load_embedded();
+
+        let mut root = Node::from_name("Copper Pickaxe").unwrap();
+        root.status = Status::Obtained;
+        root.children.push(Node::from_name("Copper Bar").unwrap());
+        root.next = Node::from_name("Wood").map(Box::new);
+
+        let tree = Tree::from_milestones(
+            "Export".to_owned(),
+            vec![Milestone {
+                image: None,
+                title: "Pre-Hardmode & <more>".to_owned(),
+                roots: vec![root],
+            }],
+        );
+
+        let svg = super::export(&tree, "tree.svg").unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Pre-Hardmode &amp; &lt;more&gt;"));
+        assert_eq!(svg.matches("<use ").count(), 3);
+
+        let png = super::export(&tree, "tree.png").unwrap();
+        let png = image::load_from_memory(&png).unwrap();
+        let size = Drawing::of(&tree).size;
+        assert_eq!((png.width(), png.height()), (size.x as u32, size.y as u32));
+
+        let mut huge = Drawing::of(&tree);
+        huge.size = vec2(20_000., 20_000.);
+        let error = huge.to_png().unwrap_err().to_string();
+        assert!(error.contains("export it as SVG"), "{error}");
+    }
+}
@@ -0,0 +1,116 @@
+use egui::Color32;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// How far along getting the item of a node is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Planned,
+    InProgress,
+    Obtained,
+    /// Not going to be obtained, like an alternative that wasn't taken.
+    Skipped,
+}
+
+impl Status {
+    pub const ALL: [Self; 4] = [Self::Planned, Self::InProgress, Self::Obtained, Self::Skipped];
+
+    pub fn is_planned(&self) -> bool {
+        *self == Self::Planned
+    }
+
+    /// Whether nothing is left to do for the node, so it doesn't need its ingredients anymore.
+    pub fn is_done(self) -> bool {
+        matches!(self, Self::Obtained | Self::Skipped)
+    }
+
+    /// The status after this one, for cycling through them with a single key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Planned => Self::InProgress,
+            Self::InProgress => Self::Obtained,
+            Self::Obtained => Self::Skipped,
+            Self::Skipped => Self::Planned,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Planned => "Planned",
+            Self::InProgress => "In progress",
+            Self::Obtained => "Obtained",
+            Self::Skipped => "Skipped",
+        }
+    }
+
+    /// The tint for the icon of a node with this status.
+    pub fn tint(self) -> Color32 {
+        match self {
+            Self::Planned | Self::InProgress => Color32::WHITE,
+            Self::Obtained => Color32::from_gray(96),
+            Self::Skipped => Color32::from_gray(64),
+        }
+    }
+
+    /// The color of the badge for this status, if it has one.
+    pub fn color(self) -> Option<Color32> {
+        match self {
+            Self::Planned => None,
+            Self::InProgress => Some(Color32::from_rgb(0xf0, 0xa0, 0x20)),
+            Self::Obtained => Some(Color32::from_rgb(0x40, 0xb0, 0x40)),
+            Self::Skipped => Some(Color32::from_gray(110)),
+        }
+    }
+}
+
+/// Also accepts the `obtained` flag trees were saved with before nodes had a status.
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Obtained(bool),
+            Status(Named),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Named {
+            Planned,
+            InProgress,
+            Obtained,
+            Skipped,
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Obtained(true) => Self::Obtained,
+            Repr::Obtained(false) => Self::Planned,
+            Repr::Status(Named::Planned) => Self::Planned,
+            Repr::Status(Named::InProgress) => Self::InProgress,
+            Repr::Status(Named::Obtained) => Self::Obtained,
+            Repr::Status(Named::Skipped) => Self::Skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        let status = |json: &str| serde_json::from_str::<Status>(json).unwrap();
+
+        for s in Status::ALL {
+            assert_eq!(status(&serde_json::to_string(&s).unwrap()), s);
+        }
+
+        assert_eq!(status(r#""in_progress""#), Status::InProgress);
+        assert_eq!(status("true"), Status::Obtained);
+        assert_eq!(status("false"), Status::Planned);
+
+        assert!(serde_json::from_str::<Status>(r#""done""#).is_err());
+        assert!(serde_json::from_str::<Status>("1").is_err());
+    }
+}
@@ -1,7 +1,6 @@
 use egui::{
-    generate_loader_id,
+    Vec2, generate_loader_id,
     load::{Bytes, BytesLoadResult, BytesLoader, BytesPoll, LoadError},
-    Vec2,
 };
 
 pub struct Loader {}
@@ -22,19 +21,20 @@ impl BytesLoader for Loader {
             return Err(LoadError::NotSupported);
         };
 
-        wiki_data::IMAGES
-            .iter()
-            .find(|i| i.name == name)
-            .map_or_else(
-                || Err(LoadError::Loading("File not found".to_string())),
-                |i| {
-                    Ok(BytesPoll::Ready {
-                        size: Some(Vec2::new(i.width as f32, i.height as f32)),
-                        bytes: Bytes::Static(i.data),
-                        mime: None,
-                    })
-                },
-            )
+        let Some(packs) = crate::pack::try_get() else {
+            return Err(LoadError::Loading("Data packs not loaded".to_string()));
+        };
+
+        packs.image_by_name(name).map_or_else(
+            || Err(LoadError::Loading("File not found".to_string())),
+            |i| {
+                Ok(BytesPoll::Ready {
+                    size: Some(Vec2::new(i.width.to_native() as f32, i.height.to_native() as f32)),
+                    bytes: Bytes::Static(i.data.as_slice()),
+                    mime: None,
+                })
+            },
+        )
     }
 
     fn forget(&self, _: &str) {
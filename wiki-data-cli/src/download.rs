@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use anyhow::Context;
 use indicatif::ProgressStyle;
 use itertools::Itertools as _;
-use serde::Deserialize;
+use serde::{Deserialize, de::DeserializeOwned};
 use tracing::{Span, info_span};
 use tracing_indicatif::span_ext::IndicatifSpanExt as _;
 use url::Url;
@@ -12,13 +12,10 @@ use wiki_data::{
     ImageLocation,
     image::{Image, WikiImageInfoPage},
     item::RawItem,
+    recipe::RawRecipe,
 };
 
-const USER_AGENT: &str = concat!(
-    "terratree-wiki-data-cli/",
-    env!("CARGO_PKG_VERSION"),
-    " (https://github.com/darkwater/terratree)",
-);
+use crate::{http::Client, source::Source};
 
 #[derive(Debug, Default, Deserialize)]
 pub struct CargoQuery<T> {
@@ -36,27 +33,25 @@ pub struct Count {
     pub count: String,
 }
 
-#[tracing::instrument(fields(indicatif.pb_show))]
-pub async fn count() -> anyhow::Result<usize> {
+async fn get_json<T: DeserializeOwned>(client: &impl Client, url: Url) -> anyhow::Result<T> {
+    Ok(serde_json::from_slice(&client.get(url).await?)?)
+}
+
+#[tracing::instrument(skip(client, source), fields(indicatif.pb_show))]
+pub async fn count(client: &impl Client, source: &Source, table: &str) -> anyhow::Result<usize> {
     let url = Url::parse_with_params(
-        "https://terraria.wiki.gg/api.php",
+        &source.api,
         [
             ("format", "json"),
             ("action", "cargoquery"),
-            ("tables", "Items"),
+            ("tables", table),
             ("limit", "max"),
             ("fields", "count(*)"),
         ],
-    )
-    .unwrap();
-
-    surf::get(url)
-        .header("User-Agent", USER_AGENT)
-        .await
-        .map_err(|e| e.into_inner())?
-        .body_json::<CargoQuery<Count>>()
-        .await
-        .map_err(|e| e.into_inner())?
+    )?;
+
+    get_json::<CargoQuery<Count>>(client, url)
+        .await?
         .cargoquery
         .into_iter()
         .next()
@@ -68,67 +63,86 @@ pub async fn count() -> anyhow::Result<usize> {
     // .map(|v: usize| v / 10)
 }
 
-#[tracing::instrument(fields(indicatif.pb_show))]
-pub async fn items(offset: usize) -> anyhow::Result<Vec<RawItem>> {
-    tracing::info!("fetching items from offset {}", offset);
+#[tracing::instrument(skip(client, source, fields), fields(indicatif.pb_show))]
+pub async fn rows<T: DeserializeOwned>(
+    client: &impl Client,
+    source: &Source,
+    table: &str,
+    fields: &[String],
+    order_by: &str,
+    offset: usize,
+) -> anyhow::Result<Vec<T>> {
+    tracing::info!("fetching {} from offset {}", table, offset);
 
     let url = Url::parse_with_params(
-        "https://terraria.wiki.gg/api.php",
+        &source.api,
         [
             ("format", "json"),
             ("action", "cargoquery"),
-            ("tables", "Items"),
+            ("tables", table),
             ("limit", "max"),
             ("offset", &offset.to_string()),
-            ("order_by", "name"),
-            ("fields", &RawItem::fields().join(",")),
+            ("order_by", source.column(order_by)),
+            ("fields", &source.query_fields(fields).join(",")),
         ],
-    )
-    .unwrap();
-
-    Ok(surf::get(url)
-        .header("User-Agent", USER_AGENT)
-        .await
-        .map_err(|e| e.into_inner())?
-        .body_json::<CargoQuery<RawItem>>()
-        .await
-        .map_err(|e| e.into_inner())?
+    )?;
+
+    Ok(get_json::<CargoQuery<T>>(client, url)
+        .await?
         .cargoquery
         .into_iter()
         .map(|entry| entry.title)
         .collect::<Vec<_>>())
 }
 
-pub async fn all_items() -> anyhow::Result<Vec<RawItem>> {
-    let span = info_span!("Downloading items", indicatif.pb_show = true);
+async fn all_rows<T: DeserializeOwned>(
+    client: &impl Client,
+    source: &Source,
+    table: &str,
+    fields: &[String],
+    order_by: &str,
+) -> anyhow::Result<Vec<T>> {
+    let span = info_span!("Downloading rows", table, indicatif.pb_show = true);
     span.pb_set_style(&ProgressStyle::default_bar());
     let _span_enter = span.enter();
 
-    let count = count().await?;
+    let count = count(client, source, table).await?;
     Span::current().pb_set_length(count as u64);
 
     let mut out = Vec::with_capacity(count);
 
     loop {
-        let mut new_items = items(out.len()).await?;
-        if out.len() >= count || new_items.is_empty() {
+        let mut new_rows = rows(client, source, table, fields, order_by, out.len()).await?;
+        if out.len() >= count || new_rows.is_empty() {
             break;
         }
 
-        Span::current().pb_inc(new_items.len() as u64);
+        Span::current().pb_inc(new_rows.len() as u64);
 
-        out.append(&mut new_items);
+        out.append(&mut new_rows);
     }
 
     Ok(out)
 }
 
-#[tracing::instrument(fields(indicatif.pb_show))]
-pub async fn images(titles: Vec<String>) -> anyhow::Result<Vec<ImageLocation>> {
+pub async fn all_items(client: &impl Client, source: &Source) -> anyhow::Result<Vec<RawItem>> {
+    all_rows(client, source, &source.items_table, &RawItem::fields(), "name").await
+}
+
+pub async fn all_recipes(client: &impl Client, source: &Source) -> anyhow::Result<Vec<RawRecipe>> {
+    all_rows(client, source, &source.recipes_table, &RawRecipe::fields(), "result").await
+}
+
+#[tracing::instrument(skip(client, source), fields(indicatif.pb_show))]
+pub async fn images(
+    client: &impl Client,
+    source: &Source,
+    titles: Vec<String>,
+) -> anyhow::Result<Vec<ImageLocation>> {
     tracing::info!("fetching {} image infos", titles.len());
 
     let url = Url::parse_with_params(
-        "https://terraria.wiki.gg/api.php",
+        &source.api,
         [
             ("format", "json"),
             ("action", "query"),
@@ -136,8 +150,7 @@ pub async fn images(titles: Vec<String>) -> anyhow::Result<Vec<ImageLocation>> {
             ("prop", "imageinfo"),
             ("iiprop", "url|size"),
         ],
-    )
-    .unwrap();
+    )?;
 
     #[derive(Debug, Default, Deserialize)]
     pub struct Query {
@@ -158,13 +171,8 @@ pub async fn images(titles: Vec<String>) -> anyhow::Result<Vec<ImageLocation>> {
     // );
     // todo!();
 
-    Ok(surf::get(url)
-        .header("User-Agent", USER_AGENT)
-        .await
-        .map_err(|e| e.into_inner())?
-        .body_json::<Query>()
-        .await
-        .map_err(|e| e.into_inner())?
+    Ok(get_json::<Query>(client, url)
+        .await?
         .query
         .pages
         .into_values()
@@ -180,15 +188,9 @@ pub async fn images(titles: Vec<String>) -> anyhow::Result<Vec<ImageLocation>> {
         .collect::<Vec<_>>())
 }
 
-#[tracing::instrument(fields(indicatif.pb_show))]
-pub async fn image(image: &ImageLocation, client: &mut surf::Client) -> anyhow::Result<Image> {
-    let data = client
-        .get(&image.url)
-        .await
-        .map_err(|e| e.into_inner())?
-        .body_bytes()
-        .await
-        .map_err(|e| e.into_inner())?;
+#[tracing::instrument(skip(client), fields(indicatif.pb_show))]
+pub async fn image(client: &impl Client, image: &ImageLocation) -> anyhow::Result<Image> {
+    let data = client.get(Url::parse(&image.url)?).await?;
 
     #[derive(Deserialize)]
     struct ErrorResponse {
@@ -211,3 +213,135 @@ pub async fn image(image: &ImageLocation, client: &mut surf::Client) -> anyhow::
         height: image.height,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use wiki_data::{Recipe, recipe::RecipeGraph};
+
+    use super::*;
+    use crate::{fixture::Server, http::SurfClient};
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"))
+    }
+
+    fn serve() -> (Server, Source, SurfClient) {
+        let server = Server::start(fixtures(), "127.0.0.1:0").unwrap();
+        let source = Source {
+            api: server.url().join("api.php").unwrap().to_string(),
+            ..Source::default()
+        };
+        let client = SurfClient::new(&source.user_agent);
+
+        (server, source, client)
+    }
+
+    /// The locations of the images in the fixtures, as served by the fixture server.
+    async fn image_locations(client: &SurfClient, source: &Source) -> Vec<ImageLocation> {
+        let titles = vec!["Copper Pickaxe.png".to_owned(), "Wood.png".to_owned()];
+        let mut locations = images(client, source, titles).await.unwrap();
+        locations.sort_by(|a, b| a.name.cmp(&b.name));
+        locations
+    }
+
+    #[async_std::test]
+    async fn count_and_items() {
+        let (_server, source, client) = serve();
+
+        assert_eq!(count(&client, &source, "Items").await.unwrap(), 2);
+
+        let items = all_items(&client, &source).await.unwrap();
+        let names = items.iter().map(|i| i.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["Copper Pickaxe", "Wood"]);
+    }
+
+    #[async_std::test]
+    async fn recipe_graph() {
+        let (_server, source, client) = serve();
+
+        let recipes = all_recipes(&client, &source)
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(Recipe::from_raw)
+            .collect::<Vec<_>>();
+        assert_eq!(recipes.len(), 5);
+
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&recipes).unwrap();
+        let archived =
+            rkyv::access::<rkyv::Archived<Vec<Recipe>>, rkyv::rancor::Error>(bytes.leak()).unwrap();
+        let graph = RecipeGraph::new(archived);
+
+        let pickaxe = graph.producing("Copper Pickaxe").collect::<Vec<_>>();
+        assert_eq!(pickaxe.len(), 1);
+        let ingredients = pickaxe[0]
+            .ingredients
+            .iter()
+            .map(|i| (i.name.as_str(), i.amount.to_native()))
+            .collect::<Vec<_>>();
+        assert_eq!(ingredients, [("Copper Bar", 12), ("Wood", 4)]);
+        assert_eq!(pickaxe[0].stations[0].to_string(), "Iron Anvil or Lead Anvil");
+
+        let bar = graph.producing("Copper Bar").next().unwrap();
+        assert_eq!(bar.ingredients[0].name, "Copper Ore");
+        assert_eq!(bar.stations[0].to_string(), "Furnace");
+
+        let mut using_wood = graph
+            .using("Wood")
+            .map(|r| r.result.as_str())
+            .collect::<Vec<_>>();
+        using_wood.sort();
+        assert_eq!(using_wood, ["Copper Pickaxe", "Work Bench"]);
+
+        let torch = graph.producing("Torch").next().unwrap();
+        assert_eq!(torch.amount, 3);
+        assert!(torch.ingredients.iter().any(|i| i.is_group()));
+        assert!(graph.using("Any Wood").any(|r| r.result == "Torch"));
+
+        assert_eq!(graph.producing("Wood").count(), 0);
+    }
+
+    #[async_std::test]
+    async fn image_infos() {
+        let (server, source, client) = serve();
+
+        let locations = image_locations(&client, &source).await;
+
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].name, "Copper Pickaxe.png");
+        assert_eq!(
+            locations[0].url,
+            server
+                .url()
+                .join("images/5/5f/Copper_Pickaxe.png")
+                .unwrap()
+                .as_str()
+        );
+        assert_eq!((locations[1].width, locations[1].height), (24, 22));
+    }
+
+    #[async_std::test]
+    async fn image_files() {
+        let (server, source, client) = serve();
+
+        for location in image_locations(&client, &source).await {
+            let file = location.url.rsplit('/').next().unwrap().to_owned();
+
+            let downloaded = image(&client, &location).await.unwrap();
+            let expected = std::fs::read(fixtures().join("files").join(file)).unwrap();
+            assert_eq!(downloaded.data, expected);
+        }
+
+        let location = ImageLocation {
+            name: "Ratelimited.png".to_owned(),
+            url: server.url().join("Ratelimited.png").unwrap().to_string(),
+            width: 2,
+            height: 2,
+        };
+
+        let err = image(&client, &location).await.unwrap_err();
+        assert!(err.to_string().contains("ratelimited"));
+    }
+}
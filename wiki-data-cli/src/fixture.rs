@@ -0,0 +1,191 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, thread::JoinHandle};
+
+use anyhow::Context as _;
+use url::Url;
+
+use crate::http::Client;
+
+/// The file a response to `url` is recorded in, relative to the fixture directory.
+///
+/// Cargo queries are named after their table and offset, like `cargoquery-Items-0.json`, image
+/// info queries after a hash of the requested titles, and anything else (image files) after the
+/// last segment of the path, under `files/`.
+pub fn fixture_name(url: &Url) -> String {
+    let query = url.query_pairs().collect::<HashMap<_, _>>();
+    let param = |key: &str| query.get(key).map(|v| v.as_ref()).unwrap_or_default();
+
+    match param("action") {
+        "cargoquery" if param("fields") == "count(*)" => {
+            format!("cargoquery-{}-count.json", param("tables"))
+        }
+        "cargoquery" => {
+            let offset = query.get("offset").map_or("0", |v| v.as_ref());
+            format!("cargoquery-{}-{offset}.json", param("tables"))
+        }
+        "query" => format!("imageinfo-{:016x}.json", fnv1a(param("titles").as_bytes())),
+        _ => {
+            let file = url
+                .path_segments()
+                .and_then(|mut s| s.next_back())
+                .unwrap_or_default();
+
+            format!("files/{file}")
+        }
+    }
+}
+
+/// A hash that stays the same across Rust versions, so fixture names do too.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Passes requests through to another client and saves every response as a fixture.
+pub struct Recorder<C> {
+    inner: C,
+    dir: PathBuf,
+}
+
+impl<C> Recorder<C> {
+    pub fn new(inner: C, dir: PathBuf) -> Self {
+        Self { inner, dir }
+    }
+}
+
+impl<C: Client> Client for Recorder<C> {
+    async fn get(&self, url: Url) -> anyhow::Result<Vec<u8>> {
+        let path = self.dir.join(fixture_name(&url));
+        let body = self.inner.get(url).await?;
+
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, &body)?;
+
+        Ok(body)
+    }
+}
+
+/// Answers requests from recorded fixtures, without touching the network.
+pub struct Replay {
+    dir: PathBuf,
+}
+
+impl Replay {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl Client for Replay {
+    async fn get(&self, url: Url) -> anyhow::Result<Vec<u8>> {
+        let name = fixture_name(&url);
+
+        std::fs::read(self.dir.join(&name)).with_context(|| format!("no fixture {name} for {url}"))
+    }
+}
+
+/// Serves recorded fixtures over HTTP on localhost, standing in for the wiki. Point a source
+/// profile's `api` at `<url>/api.php` to run the pipeline against it.
+pub struct Server {
+    server: Arc<tiny_http::Server>,
+    thread: Option<JoinHandle<()>>,
+    url: Url,
+}
+
+impl Server {
+    /// Starts serving `dir` on `addr`, like "127.0.0.1:0" for any free port.
+    pub fn start(dir: PathBuf, addr: &str) -> anyhow::Result<Self> {
+        let server = Arc::new(tiny_http::Server::http(addr).map_err(|e| anyhow::anyhow!(e))?);
+
+        let url = Url::parse(&format!("http://{}/", server.server_addr()))?;
+
+        let thread = std::thread::spawn({
+            let server = server.clone();
+            let url = url.clone();
+            let replay = Replay::new(dir);
+
+            move || {
+                for request in server.incoming_requests() {
+                    let body = url
+                        .join(request.url())
+                        .map_err(anyhow::Error::from)
+                        .and_then(|request_url| {
+                            let body = async_std::task::block_on(replay.get(request_url.clone()))?;
+
+                            match fixture_name(&request_url).starts_with("imageinfo-") {
+                                true => rewrite_image_urls(&body, &url),
+                                false => Ok(body),
+                            }
+                        });
+
+                    let res = match body {
+                        Ok(body) => request.respond(tiny_http::Response::from_data(body)),
+                        Err(e) => {
+                            tracing::warn!("{e:#}");
+                            request.respond(tiny_http::Response::empty(404))
+                        }
+                    };
+
+                    if let Err(e) = res {
+                        tracing::warn!("failed to respond: {e}");
+                    }
+                }
+            }
+        });
+
+        Ok(Self { server, thread: Some(thread), url })
+    }
+
+    /// The server's base URL, like `http://127.0.0.1:8000/`.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Serves requests until the process is stopped.
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+/// Points the image URLs in a recorded image info response at `base`, keeping their paths, so
+/// the images are downloaded from the [`Server`] too instead of the wiki.
+fn rewrite_image_urls(body: &[u8], base: &Url) -> anyhow::Result<Vec<u8>> {
+    let mut response = serde_json::from_slice::<serde_json::Value>(body)?;
+
+    let pages = response
+        .pointer_mut("/query/pages")
+        .and_then(|pages| pages.as_object_mut())
+        .into_iter()
+        .flat_map(|pages| pages.values_mut());
+
+    for page in pages {
+        let infos = page
+            .get_mut("imageinfo")
+            .and_then(|infos| infos.as_array_mut())
+            .into_iter()
+            .flatten();
+
+        for info in infos {
+            if let Some(url) = info.get_mut("url")
+                && let Some(recorded) = url.as_str()
+            {
+                let path = Url::parse(recorded)?.path().to_owned();
+                *url = base.join(&path)?.to_string().into();
+            }
+        }
+    }
+
+    Ok(serde_json::to_vec(&response)?)
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.server.unblock();
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use rkyv::{api::high::HighSerializer, rancor, ser::allocator::ArenaHandle, util::AlignedVec};
 use wiki_data::item::Item;
 
 pub fn rust(items: &[Item], path: PathBuf) -> anyhow::Result<()> {
@@ -8,8 +9,12 @@ pub fn rust(items: &[Item], path: PathBuf) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn msgpack(items: &[Item], path: PathBuf) -> anyhow::Result<()> {
-    let out = rmp_serde::to_vec(items)?;
+/// Writes `data` as an rkyv archive that wiki-data can access in place.
+pub fn archive<T>(data: &T, path: PathBuf) -> anyhow::Result<()>
+where
+    T: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+{
+    let out = rkyv::to_bytes::<rancor::Error>(data)?;
     std::fs::write(path, out)?;
 
     Ok(())
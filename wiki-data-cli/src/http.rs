@@ -0,0 +1,56 @@
+use std::future::Future;
+
+use url::Url;
+
+use crate::fixture::{Recorder, Replay};
+
+/// Something that can fetch URLs. Everything in `download` goes through this, so the pipeline
+/// can run against recorded fixtures instead of the live wiki.
+pub trait Client {
+    fn get(&self, url: Url) -> impl Future<Output = anyhow::Result<Vec<u8>>>;
+}
+
+/// Fetches URLs over the network.
+pub struct SurfClient {
+    client: surf::Client,
+    user_agent: String,
+}
+
+impl SurfClient {
+    pub fn new(user_agent: impl Into<String>) -> Self {
+        Self {
+            client: surf::Client::new(),
+            user_agent: user_agent.into(),
+        }
+    }
+}
+
+impl Client for SurfClient {
+    async fn get(&self, url: Url) -> anyhow::Result<Vec<u8>> {
+        self.client
+            .get(url)
+            .header("User-Agent", self.user_agent.as_str())
+            .await
+            .map_err(|e| e.into_inner())?
+            .body_bytes()
+            .await
+            .map_err(|e| e.into_inner())
+    }
+}
+
+/// The client picked on the command line.
+pub enum AnyClient {
+    Surf(SurfClient),
+    Record(Recorder<SurfClient>),
+    Replay(Replay),
+}
+
+impl Client for AnyClient {
+    async fn get(&self, url: Url) -> anyhow::Result<Vec<u8>> {
+        match self {
+            AnyClient::Surf(client) => client.get(url).await,
+            AnyClient::Record(client) => client.get(url).await,
+            AnyClient::Replay(client) => client.get(url).await,
+        }
+    }
+}
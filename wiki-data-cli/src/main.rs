@@ -1,13 +1,7 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
-    path::PathBuf,
-};
+use std::path::PathBuf;
 
 use clap::Parser;
 use rand::prelude::IndexedRandom as _;
-use rmp_serde::config::BytesMode;
-use serde::Serialize as _;
 use tracing::level_filters::LevelFilter;
 use tracing_indicatif::{
     IndicatifLayer,
@@ -17,17 +11,36 @@ use tracing_subscriber::{
     Layer, fmt::format::DefaultFields, layer::SubscriberExt as _, util::SubscriberInitExt as _,
 };
 
-use wiki_data::{
-    ImageLocation,
-    image::Image,
-    item::{Item, RawItem},
+use wiki_data::Pack;
+
+use self::{
+    fixture::{Recorder, Replay},
+    http::{AnyClient, SurfClient},
+    source::Source,
 };
 
 mod download;
+mod fixture;
 mod generate;
+mod http;
+mod source;
+mod steps;
+mod sync;
 
 #[derive(Parser)]
 struct Args {
+    /// TOML profile of the wiki to use, for modded content. Defaults to the Terraria wiki.
+    #[clap(long, global = true)]
+    source: Option<PathBuf>,
+
+    /// Save every response into this directory as a fixture.
+    #[clap(long, global = true, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Answer requests from fixtures in this directory instead of the network.
+    #[clap(long, global = true)]
+    replay: Option<PathBuf>,
+
     #[clap(subcommand)]
     subcmd: Subcommand,
 }
@@ -35,15 +48,50 @@ struct Args {
 #[derive(clap::Subcommand)]
 enum Subcommand {
     Download {},
+    /// Parse the downloaded items, writing a report of the ones that failed to parse-report.json
+    /// in the work directory.
     Parse {
         #[clap(long)]
         generate_rust: Option<PathBuf>,
 
         #[clap(long)]
-        generate_msgpack: Option<PathBuf>,
+        generate_archive: Option<PathBuf>,
     },
     LocateImages {},
     DownloadImages {},
+    DownloadRecipes {},
+    ParseRecipes {
+        #[clap(long)]
+        generate_archive: Option<PathBuf>,
+    },
+    /// Bundles the item, recipe and image archives into a single data pack the app can load at
+    /// runtime.
+    Pack {
+        /// Directory with the item, recipe and image archives. Defaults to where the source
+        /// writes them.
+        #[clap(long)]
+        archives: Option<PathBuf>,
+
+        output: PathBuf,
+    },
+    /// Runs the whole pipeline: downloads items and recipes, locates and downloads images, and
+    /// parses everything into archives. Steps whose inputs haven't changed are skipped.
+    Sync {
+        /// Where intermediate files go. Defaults to the source's work directory.
+        #[clap(long)]
+        work_dir: Option<PathBuf>,
+
+        /// Where the archives go. Defaults to the source's archive directory.
+        #[clap(long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Serves recorded fixtures over HTTP, standing in for the wiki.
+    ServeFixtures {
+        dir: PathBuf,
+
+        #[clap(long, default_value = "127.0.0.1:8000")]
+        addr: String,
+    },
 }
 
 #[async_std::main]
@@ -62,179 +110,93 @@ async fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
-    match args.subcmd {
-        Subcommand::Download {} => {
-            let items = download::all_items().await?;
-
-            tracing::info!("downloaded {} items", items.len());
-
-            let out = rmp_serde::to_vec(&items)?;
-            std::fs::write("raw-items.bin", out)?;
+    let source = args
+        .source
+        .map(Source::load)
+        .transpose()?
+        .unwrap_or_default();
+    let work_dir = source.work_dir();
+    std::fs::create_dir_all(&work_dir)?;
+
+    let client = match (args.record, args.replay) {
+        (_, Some(dir)) => AnyClient::Replay(Replay::new(dir)),
+        (Some(dir), None) => {
+            AnyClient::Record(Recorder::new(SurfClient::new(&source.user_agent), dir))
         }
-        Subcommand::Parse { generate_rust, generate_msgpack } => {
-            let raw_items = parse_raw_items()?;
-            let image_locations = parse_image_locations()?
-                .into_iter()
-                .map(|il| (il.name.clone(), il))
-                .collect::<HashMap<_, _>>();
-
-            let pb = indicatif::ProgressBar::new(raw_items.len() as u64);
-            let mut items = pb
-                .wrap_iter(raw_items.iter())
-                .filter_map(|raw_item| {
-                    let res = Item::from_raw(
-                        raw_item,
-                        image_locations.get(raw_item.imagefile()?.as_str()).cloned(),
-                    );
-
-                    if res.is_none()
-                        && let Some(item_id) = raw_item.itemid()
-                    {
-                        tracing::warn!(
-                            "failed to parse item {:?} (id {:?})",
-                            raw_item.name(),
-                            item_id,
-                        );
-                    }
-
-                    res
-                })
-                .collect::<Vec<Item>>();
-
-            pb.finish();
-
-            items.sort_by_key(|i| i.item_id);
+        (None, None) => AnyClient::Surf(SurfClient::new(&source.user_agent)),
+    };
+
+    match args.subcmd {
+        Subcommand::Download {} => steps::download_items(&client, &source, &work_dir).await?,
+        Subcommand::Parse { generate_rust, generate_archive } => {
+            let items = steps::parse_items(&work_dir)?;
 
             if let Some(path) = generate_rust {
                 generate::rust(&items, path)?
             }
 
-            if let Some(path) = generate_msgpack {
-                generate::msgpack(&items, path)?
+            if let Some(path) = generate_archive {
+                generate::archive(&items, path)?
             }
 
             for item in items.sample(&mut rand::rng(), 10) {
                 tracing::info!("{:#?}", item);
             }
-
-            tracing::info!("parsed {}/{} items", items.len(), raw_items.len());
-        }
-        Subcommand::LocateImages {} => {
-            let mut images = File::open("image-locations.bin")
-                .ok()
-                .and_then(|f| rmp_serde::from_read::<_, Vec<ImageLocation>>(f).ok())
-                .unwrap_or_default();
-
-            let mut items = parse_raw_items()?;
-
-            let contained_names = images
-                .iter()
-                .map(|i| dbg!(i.name.as_str()))
-                .collect::<HashSet<_>>();
-
-            items.retain(|item| {
-                if let Some(imagefile) = item.imagefile() {
-                    !contained_names.contains(imagefile.as_str())
-                } else {
-                    false
-                }
-            });
-
-            for chunk in items.chunks(50) {
-                tracing::info!("{chunk:#?}");
-
-                images.append(
-                    &mut download::images(chunk.iter().filter_map(|i| i.imagefile()).collect())
-                        .await?,
-                );
-
-                tracing::info!("{images:#?}");
-            }
-
-            images.sort_by_key(|i| i.name.clone());
-
-            let out = rmp_serde::to_vec(&images)?;
-            std::fs::write("image-locations.bin", out)?;
-
-            tracing::info!("now have {} images", images.len());
         }
+        Subcommand::LocateImages {} => steps::locate_images(&client, &source, &work_dir).await?,
         Subcommand::DownloadImages {} => {
-            let mut out = File::open("wiki-data/src/images.bin")
-                .ok()
-                .and_then(|f| rmp_serde::from_read::<_, Vec<Image>>(f).ok())
-                .unwrap_or_default();
-
-            let mut images = parse_image_locations()?;
-
-            let downloaded = out.iter().map(|i| i.name.as_str()).collect::<HashSet<_>>();
-
-            images.retain(|i| !downloaded.contains(i.name.as_str()));
-
-            let pb = indicatif::ProgressBar::new(images.len() as u64);
-            let images = pb.wrap_iter(images.into_iter());
-
-            let mut client = surf::Client::new();
-
-            let mut backoff = 1;
-
-            'next_image: for image in images {
-                'retry: loop {
-                    match download::image(&image, &mut client).await {
-                        Ok(image) => {
-                            out.push(image);
-                            continue 'next_image;
-                        }
-                        Err(e) => {
-                            tracing::error!("failed to download {}: {}", image.name, e);
-
-                            if e.to_string().contains("ratelimited") {
-                                tracing::info!("backing off for {backoff} seconds...");
-                                async_std::task::sleep(std::time::Duration::from_secs(backoff))
-                                    .await;
-                                backoff *= 2;
-
-                                continue 'retry;
-                            } else {
-                                continue 'next_image;
-                            }
-                        }
-                    }
-                }
+            let archive_path = source.archive_dir().join("images.rkyv");
+            steps::download_images(&client, &work_dir, archive_path).await?
+        }
+        Subcommand::DownloadRecipes {} => {
+            steps::download_recipes(&client, &source, &work_dir).await?
+        }
+        Subcommand::ParseRecipes { generate_archive } => {
+            let recipes = steps::parse_recipes(&work_dir)?;
+
+            if let Some(path) = generate_archive {
+                generate::archive(&recipes, path)?
             }
 
-            pb.finish();
+            for recipe in recipes.sample(&mut rand::rng(), 10) {
+                tracing::info!("{:#?}", recipe);
+            }
+        }
+        Subcommand::Pack { archives, output } => {
+            let archives = archives.unwrap_or_else(|| source.archive_dir());
+
+            let pack = Pack {
+                namespace: source.namespace,
+                name: source.name,
+                wiki_url: source.wiki_url,
+                items: steps::read_archive(&archives.join("items.rkyv"))?,
+                recipes: steps::read_archive(&archives.join("recipes.rkyv"))?,
+                images: steps::read_archive(&archives.join("images.rkyv"))?,
+            };
+
+            tracing::info!(
+                "packing {} items, {} recipes and {} images",
+                pack.items.len(),
+                pack.recipes.len(),
+                pack.images.len(),
+            );
+
+            generate::archive(&pack, output)?;
+        }
+        Subcommand::Sync { work_dir: sync_work_dir, output_dir } => {
+            let work_dir = sync_work_dir.unwrap_or(work_dir);
+            let output_dir = output_dir.unwrap_or_else(|| source.archive_dir());
 
-            let mut bytes = Vec::new();
-            let mut serializer =
-                rmp_serde::Serializer::new(&mut bytes).with_bytes(BytesMode::ForceAll);
-            out.serialize(&mut serializer)?;
-            std::fs::write("wiki-data/src/images.bin", bytes)?;
+            for changes in sync::run(&client, &source, &work_dir, &output_dir).await? {
+                println!("{changes}");
+            }
+        }
+        Subcommand::ServeFixtures { dir, addr } => {
+            let server = fixture::Server::start(dir, &addr)?;
+            tracing::info!("serving fixtures at {}api.php", server.url());
+            server.wait();
         }
     }
 
     Ok(())
 }
-
-fn parse_raw_items() -> anyhow::Result<Vec<RawItem>> {
-    let reader = File::open("raw-items.bin")?;
-    let len = reader.metadata()?.len();
-    let pb = indicatif::ProgressBar::new(len);
-    let reader = pb.wrap_read(reader);
-
-    let raw_items = rmp_serde::from_read::<_, Vec<RawItem>>(reader)?;
-    pb.finish();
-
-    Ok(raw_items)
-}
-
-fn parse_image_locations() -> anyhow::Result<Vec<ImageLocation>> {
-    let reader = File::open("image-locations.bin")?;
-    let len = reader.metadata()?.len();
-    let pb = indicatif::ProgressBar::new(len);
-    let reader = pb.wrap_read(reader);
-
-    let image_locations = rmp_serde::from_read::<_, Vec<ImageLocation>>(reader)?;
-    pb.finish();
-
-    Ok(image_locations)
-}
@@ -0,0 +1,171 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+/// A wiki to download data from. The default is the official Terraria wiki; mods with their own
+/// wikis can be described in a TOML profile, like:
+///
+/// ```toml
+/// namespace = "calamity"
+/// name = "Calamity Mod"
+/// api = "https://calamitymod.wiki.gg/api.php"
+/// wiki_url = "https://calamitymod.wiki.gg/wiki/"
+/// items_table = "Items"
+///
+/// [fields]
+/// rare = "rarity"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Source {
+    /// Short identifier used to keep this source's files and pack apart from others.
+    pub namespace: String,
+    /// Display name, shown in the app.
+    pub name: String,
+    /// URL of the wiki's `api.php`.
+    pub api: String,
+    /// Base URL of the wiki's article pages.
+    pub wiki_url: String,
+    pub items_table: String,
+    pub recipes_table: String,
+    pub user_agent: String,
+    /// Maps our field names to the wiki's column names, where they differ.
+    pub fields: HashMap<String, String>,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self {
+            namespace: "terraria".to_owned(),
+            name: "Terraria".to_owned(),
+            api: "https://terraria.wiki.gg/api.php".to_owned(),
+            wiki_url: "https://terraria.wiki.gg/wiki/".to_owned(),
+            items_table: "Items".to_owned(),
+            recipes_table: "Recipes".to_owned(),
+            user_agent: concat!(
+                "terratree-wiki-data-cli/",
+                env!("CARGO_PKG_VERSION"),
+                " (https://github.com/darkwater/terratree)",
+            )
+            .to_owned(),
+            fields: HashMap::new(),
+        }
+    }
+}
+
+impl Source {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        Self::parse(&std::fs::read_to_string(&path)?)
+            .with_context(|| format!("invalid source profile {}", path.display()))
+    }
+
+    /// Reads a TOML profile. Everything but the namespace defaults to the official wiki's, but
+    /// the namespace has to be given so a profile can't write over the built-in archives by
+    /// accident.
+    fn parse(profile: &str) -> anyhow::Result<Self> {
+        let profile = toml::from_str::<toml::Table>(profile)?;
+
+        match profile.get("namespace") {
+            Some(toml::Value::String(namespace)) if !namespace.is_empty() => {}
+            Some(_) => anyhow::bail!("namespace must be a non-empty string"),
+            None => anyhow::bail!("missing namespace"),
+        }
+
+        Ok(profile.try_into()?)
+    }
+
+    fn is_builtin(&self) -> bool {
+        self.namespace == Self::default().namespace
+    }
+
+    /// Where intermediate files like `raw-items.bin` go. The built-in source keeps them in the
+    /// current directory, others get a directory named after their namespace.
+    pub fn work_dir(&self) -> PathBuf {
+        if self.is_builtin() {
+            PathBuf::from(".")
+        } else {
+            PathBuf::from(&self.namespace)
+        }
+    }
+
+    /// Where the item, recipe and image archives go. The built-in source writes the archives
+    /// that are embedded in wiki-data.
+    pub fn archive_dir(&self) -> PathBuf {
+        if self.is_builtin() {
+            PathBuf::from("wiki-data/src")
+        } else {
+            self.work_dir()
+        }
+    }
+
+    /// The wiki's column name for one of our fields.
+    pub fn column<'a>(&'a self, field: &'a str) -> &'a str {
+        self.fields.get(field).map_or(field, String::as_str)
+    }
+
+    /// Cargo query fields for `fields`, renaming the wiki's columns to our names where the
+    /// profile maps them.
+    pub fn query_fields(&self, fields: &[String]) -> Vec<String> {
+        fields
+            .iter()
+            .map(|field| match self.column(field) {
+                column if column == field => field.clone(),
+                column => format!("{column}={field}"),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        let source = Source::default();
+
+        assert_eq!(source.work_dir(), PathBuf::from("."));
+        assert_eq!(source.archive_dir(), PathBuf::from("wiki-data/src"));
+        assert_eq!(source.query_fields(&["name".to_owned()]), ["name"]);
+    }
+
+    #[test]
+    fn profile() {
+        let source = Source::parse(
+            r#"
+                namespace = "calamity"
+                name = "Calamity Mod"
+                api = "https://calamitymod.wiki.gg/api.php"
+
+                [fields]
+                rare = "rarity"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(source.name, "Calamity Mod");
+        assert_eq!(source.items_table, "Items");
+        assert_eq!(source.work_dir(), PathBuf::from("calamity"));
+        assert_eq!(source.archive_dir(), PathBuf::from("calamity"));
+
+        assert_eq!(source.column("rare"), "rarity");
+        assert_eq!(source.column("name"), "name");
+        assert_eq!(
+            source.query_fields(&["name".to_owned(), "rare".to_owned()]),
+            ["name", "rarity=rare"]
+        );
+    }
+
+    #[test]
+    fn profile_needs_namespace() {
+        let err = Source::parse(r#"name = "Calamity Mod""#).unwrap_err();
+        assert!(err.to_string().contains("missing namespace"));
+
+        assert!(Source::parse(r#"namespace = """#).is_err());
+        assert!(Source::parse("namespace = 1").is_err());
+
+        let source = Source::parse(r#"namespace = "terraria""#).unwrap();
+        assert_eq!(source.archive_dir(), PathBuf::from("wiki-data/src"));
+    }
+}
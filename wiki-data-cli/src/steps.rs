@@ -0,0 +1,281 @@
+//! The steps of the data pipeline. Each one reads its inputs from and writes its outputs to the
+//! work directory, except for the archives, which go wherever the caller says.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use rkyv::rancor;
+use wiki_data::{
+    ImageLocation,
+    image::Image,
+    item::{FieldError, Item, ParseError, RawItem},
+    recipe::{RawRecipe, Recipe},
+};
+
+use crate::{download, generate, http::Client, source::Source};
+
+pub const RAW_ITEMS: &str = "raw-items.bin";
+pub const RAW_RECIPES: &str = "raw-recipes.bin";
+pub const IMAGE_LOCATIONS: &str = "image-locations.bin";
+pub const PARSE_REPORT: &str = "parse-report.json";
+
+pub async fn download_items(
+    client: &impl Client,
+    source: &Source,
+    work_dir: &Path,
+) -> anyhow::Result<()> {
+    let items = download::all_items(client, source).await?;
+
+    tracing::info!("downloaded {} items", items.len());
+
+    let out = rmp_serde::to_vec(&items)?;
+    std::fs::write(work_dir.join(RAW_ITEMS), out)?;
+
+    Ok(())
+}
+
+pub async fn download_recipes(
+    client: &impl Client,
+    source: &Source,
+    work_dir: &Path,
+) -> anyhow::Result<()> {
+    let recipes = download::all_recipes(client, source).await?;
+
+    tracing::info!("downloaded {} recipes", recipes.len());
+
+    let out = rmp_serde::to_vec(&recipes)?;
+    std::fs::write(work_dir.join(RAW_RECIPES), out)?;
+
+    Ok(())
+}
+
+/// Looks up where the images of all items are hosted, skipping images that were located before.
+pub async fn locate_images(
+    client: &impl Client,
+    source: &Source,
+    work_dir: &Path,
+) -> anyhow::Result<()> {
+    let mut images = File::open(work_dir.join(IMAGE_LOCATIONS))
+        .ok()
+        .and_then(|f| rmp_serde::from_read::<_, Vec<ImageLocation>>(f).ok())
+        .unwrap_or_default();
+
+    let mut items = parse_raw_items(work_dir)?;
+
+    let contained_names = images
+        .iter()
+        .map(|i| i.name.as_str())
+        .collect::<HashSet<_>>();
+
+    items.retain(|item| {
+        if let Some(imagefile) = item.imagefile() {
+            !contained_names.contains(imagefile.as_str())
+        } else {
+            false
+        }
+    });
+
+    for chunk in items.chunks(50) {
+        images.append(
+            &mut download::images(
+                client,
+                source,
+                chunk.iter().filter_map(|i| i.imagefile()).collect(),
+            )
+            .await?,
+        );
+    }
+
+    images.sort_by_key(|i| i.name.clone());
+
+    let out = rmp_serde::to_vec(&images)?;
+    std::fs::write(work_dir.join(IMAGE_LOCATIONS), out)?;
+
+    tracing::info!("now have {} images", images.len());
+
+    Ok(())
+}
+
+/// Downloads every located image that isn't in the archive at `archive_path` yet, and writes the
+/// archive back.
+pub async fn download_images(
+    client: &impl Client,
+    work_dir: &Path,
+    archive_path: PathBuf,
+) -> anyhow::Result<()> {
+    let mut out = read_archive::<Image>(&archive_path).unwrap_or_default();
+
+    let mut images = parse_image_locations(work_dir)?;
+
+    let downloaded = out.iter().map(|i| i.name.as_str()).collect::<HashSet<_>>();
+
+    images.retain(|i| !downloaded.contains(i.name.as_str()));
+
+    let pb = indicatif::ProgressBar::new(images.len() as u64);
+    let images = pb.wrap_iter(images.into_iter());
+
+    let mut backoff = 1;
+
+    'next_image: for image in images {
+        'retry: loop {
+            match download::image(client, &image).await {
+                Ok(image) => {
+                    out.push(image);
+                    continue 'next_image;
+                }
+                Err(e) => {
+                    tracing::error!("failed to download {}: {}", image.name, e);
+
+                    if e.to_string().contains("ratelimited") {
+                        tracing::info!("backing off for {backoff} seconds...");
+                        async_std::task::sleep(std::time::Duration::from_secs(backoff)).await;
+                        backoff *= 2;
+
+                        continue 'retry;
+                    } else {
+                        continue 'next_image;
+                    }
+                }
+            }
+        }
+    }
+
+    pb.finish();
+
+    generate::archive(&out, archive_path)
+}
+
+/// Parses all downloaded items, and writes a report of the ones that were rejected or only
+/// partially parsed to the work directory.
+pub fn parse_items(work_dir: &Path) -> anyhow::Result<Vec<Item>> {
+    let raw_items = parse_raw_items(work_dir)?;
+    let image_locations = parse_image_locations(work_dir)?
+        .into_iter()
+        .map(|il| (il.name.clone(), il))
+        .collect::<HashMap<_, _>>();
+
+    let mut report = Vec::new();
+
+    let pb = indicatif::ProgressBar::new(raw_items.len() as u64);
+    let mut items = pb
+        .wrap_iter(raw_items.iter())
+        .filter_map(|raw_item| {
+            // Items without an image file are skipped, but those whose image couldn't be located
+            // are kept without one.
+            let Some(image_file) = raw_item.imagefile() else {
+                report.push(ParseError {
+                    name: raw_item.name(),
+                    item_id: raw_item.itemid(),
+                    rejected: true,
+                    fields: vec![FieldError {
+                        field: "imagefile",
+                        value: None,
+                        reason: "missing".to_owned(),
+                    }],
+                    item: None,
+                });
+                return None;
+            };
+
+            match Item::from_raw(raw_item, image_locations.get(image_file.as_str()).cloned()) {
+                Ok(item) => Some(item),
+                Err(mut error) => {
+                    if error.rejected {
+                        tracing::warn!("{error}");
+                    }
+
+                    let item = error.item.take().map(|item| *item);
+                    report.push(error);
+                    item
+                }
+            }
+        })
+        .collect::<Vec<Item>>();
+
+    pb.finish();
+
+    items.sort_by_key(|i| i.item_id);
+
+    tracing::info!("parsed {}/{} items", items.len(), raw_items.len());
+
+    let rejected = report.iter().filter(|e| e.rejected).count();
+    tracing::info!(
+        "{rejected} items rejected and {} partially parsed, see {PARSE_REPORT}",
+        report.len() - rejected,
+    );
+    std::fs::write(work_dir.join(PARSE_REPORT), serde_json::to_vec_pretty(&report)?)?;
+
+    Ok(items)
+}
+
+pub fn parse_recipes(work_dir: &Path) -> anyhow::Result<Vec<Recipe>> {
+    let raw_recipes = parse_raw_recipes(work_dir)?;
+
+    let mut recipes = raw_recipes
+        .iter()
+        .filter_map(|raw_recipe| {
+            let res = Recipe::from_raw(raw_recipe);
+
+            if res.is_none() {
+                tracing::warn!("failed to parse recipe {:?}", raw_recipe);
+            }
+
+            res
+        })
+        .collect::<Vec<Recipe>>();
+
+    recipes.sort_by(|a, b| a.result.cmp(&b.result));
+
+    tracing::info!("parsed {}/{} recipes", recipes.len(), raw_recipes.len());
+
+    Ok(recipes)
+}
+
+fn parse_raw_items(work_dir: &Path) -> anyhow::Result<Vec<RawItem>> {
+    let reader = File::open(work_dir.join(RAW_ITEMS))?;
+    let len = reader.metadata()?.len();
+    let pb = indicatif::ProgressBar::new(len);
+    let reader = pb.wrap_read(reader);
+
+    let raw_items = rmp_serde::from_read::<_, Vec<RawItem>>(reader)?;
+    pb.finish();
+
+    Ok(raw_items)
+}
+
+fn parse_image_locations(work_dir: &Path) -> anyhow::Result<Vec<ImageLocation>> {
+    let reader = File::open(work_dir.join(IMAGE_LOCATIONS))?;
+    let len = reader.metadata()?.len();
+    let pb = indicatif::ProgressBar::new(len);
+    let reader = pb.wrap_read(reader);
+
+    let image_locations = rmp_serde::from_read::<_, Vec<ImageLocation>>(reader)?;
+    pb.finish();
+
+    Ok(image_locations)
+}
+
+fn parse_raw_recipes(work_dir: &Path) -> anyhow::Result<Vec<RawRecipe>> {
+    let reader = File::open(work_dir.join(RAW_RECIPES))?;
+    let len = reader.metadata()?.len();
+    let pb = indicatif::ProgressBar::new(len);
+    let reader = pb.wrap_read(reader);
+
+    let raw_recipes = rmp_serde::from_read::<_, Vec<RawRecipe>>(reader)?;
+    pb.finish();
+
+    Ok(raw_recipes)
+}
+
+pub fn read_archive<T>(path: &Path) -> anyhow::Result<Vec<T>>
+where
+    T: rkyv::Archive,
+    T::Archived: for<'a> rkyv::bytecheck::CheckBytes<rkyv::api::high::HighValidator<'a, rancor::Error>>
+        + rkyv::Deserialize<T, rkyv::api::high::HighDeserializer<rancor::Error>>,
+{
+    let bytes = std::fs::read(path)?;
+    Ok(rkyv::from_bytes::<Vec<T>, rancor::Error>(&bytes)?)
+}
@@ -0,0 +1,375 @@
+//! The `sync` subcommand: runs the whole pipeline, skipping steps whose inputs haven't changed
+//! since the last run, and reports what changed in the archives.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use wiki_data::{Image, Item, Recipe};
+
+use crate::{
+    fixture::fnv1a,
+    http::Client,
+    source::Source,
+    steps::{self, IMAGE_LOCATIONS, RAW_ITEMS, RAW_RECIPES},
+};
+
+const STATE: &str = "sync-state.json";
+
+/// Hashes of the inputs of each step, as of the last time it ran.
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    inputs: HashMap<String, u64>,
+}
+
+struct Sync<'a> {
+    work_dir: &'a Path,
+    state: State,
+}
+
+impl Sync<'_> {
+    /// Returns the hash of the inputs of `step` if it needs to run: because its inputs changed or
+    /// one of its outputs is missing.
+    fn needs_run(
+        &self,
+        step: &str,
+        inputs: &[&str],
+        outputs: &[&Path],
+    ) -> anyhow::Result<Option<u64>> {
+        let mut bytes = Vec::new();
+        for input in inputs {
+            bytes.extend_from_slice(input.as_bytes());
+            bytes.extend(std::fs::read(self.work_dir.join(input))?);
+        }
+        let hash = fnv1a(&bytes);
+
+        if self.state.inputs.get(step) == Some(&hash) && outputs.iter().all(|o| o.exists()) {
+            tracing::info!("skipping {step}, inputs unchanged");
+            Ok(None)
+        } else {
+            Ok(Some(hash))
+        }
+    }
+
+    fn ran(&mut self, step: &str, hash: u64) -> anyhow::Result<()> {
+        self.state.inputs.insert(step.to_owned(), hash);
+        std::fs::write(self.work_dir.join(STATE), serde_json::to_vec_pretty(&self.state)?)?;
+
+        Ok(())
+    }
+}
+
+/// Runs the pipeline for `source`, returning what changed in each archive.
+pub async fn run(
+    client: &impl Client,
+    source: &Source,
+    work_dir: &Path,
+    output_dir: &Path,
+) -> anyhow::Result<Vec<Changes>> {
+    std::fs::create_dir_all(work_dir)?;
+    std::fs::create_dir_all(output_dir)?;
+
+    let state = std::fs::read(work_dir.join(STATE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+
+    let mut sync = Sync { work_dir, state };
+    let mut summary = Vec::new();
+
+    steps::download_items(client, source, work_dir).await?;
+    steps::download_recipes(client, source, work_dir).await?;
+
+    let locations = work_dir.join(IMAGE_LOCATIONS);
+    if let Some(hash) = sync.needs_run("locate-images", &[RAW_ITEMS], &[&locations])? {
+        steps::locate_images(client, source, work_dir).await?;
+        sync.ran("locate-images", hash)?;
+    }
+
+    let images_path = output_dir.join("images.rkyv");
+    summary.push(match sync.needs_run("download-images", &[IMAGE_LOCATIONS], &[&images_path])? {
+        Some(hash) => {
+            let old = steps::read_archive::<Image>(&images_path).unwrap_or_default();
+            steps::download_images(client, work_dir, images_path.clone()).await?;
+            let new = steps::read_archive::<Image>(&images_path)?;
+            sync.ran("download-images", hash)?;
+
+            Changes::between("images", &old, &new, |i| i.name.clone())
+        }
+        None => Changes::skipped("images"),
+    });
+
+    let items_path = output_dir.join("items.rkyv");
+    summary.push(
+        match sync.needs_run("parse-items", &[RAW_ITEMS, IMAGE_LOCATIONS], &[&items_path])? {
+            Some(hash) => {
+                let old = steps::read_archive::<Item>(&items_path).unwrap_or_default();
+                let new = steps::parse_items(work_dir)?;
+                crate::generate::archive(&new, items_path)?;
+                sync.ran("parse-items", hash)?;
+
+                Changes::between("items", &old, &new, |i| i.name.clone())
+            }
+            None => Changes::skipped("items"),
+        },
+    );
+
+    let recipes_path = output_dir.join("recipes.rkyv");
+    summary.push(match sync.needs_run("parse-recipes", &[RAW_RECIPES], &[&recipes_path])? {
+        Some(hash) => {
+            let old = steps::read_archive::<Recipe>(&recipes_path).unwrap_or_default();
+            let new = steps::parse_recipes(work_dir)?;
+            crate::generate::archive(&new, recipes_path)?;
+            sync.ran("parse-recipes", hash)?;
+
+            Changes::between("recipes", &old, &new, |r| r.result.clone())
+        }
+        None => Changes::skipped("recipes"),
+    });
+
+    Ok(summary)
+}
+
+/// What changed in one archive. Entries are compared by key, and considered changed if any of
+/// their data differs.
+pub struct Changes {
+    kind: &'static str,
+    skipped: bool,
+    added: Vec<String>,
+    changed: Vec<String>,
+    removed: Vec<String>,
+}
+
+/// How many names of each kind of change are listed before the rest is summarized.
+const MAX_LISTED: usize = 20;
+
+impl Changes {
+    fn skipped(kind: &'static str) -> Self {
+        Self {
+            kind,
+            skipped: true,
+            added: Vec::new(),
+            changed: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    fn between<T: Serialize>(
+        kind: &'static str,
+        old: &[T],
+        new: &[T],
+        key: impl Fn(&T) -> String,
+    ) -> Self {
+        // Several entries can share a key, like the recipes for one item, so hash them together.
+        let hashes = |entries: &[T]| {
+            let mut grouped = BTreeMap::<String, Vec<u8>>::new();
+            for entry in entries {
+                grouped
+                    .entry(key(entry))
+                    .or_default()
+                    .extend(rmp_serde::to_vec(entry).unwrap_or_default());
+            }
+            grouped
+                .into_iter()
+                .map(|(key, bytes)| (key, fnv1a(&bytes)))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let old = hashes(old);
+        let new = hashes(new);
+
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+
+        for (key, hash) in &new {
+            match old.get(key) {
+                None => added.push(key.clone()),
+                Some(old_hash) if old_hash != hash => changed.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+
+        let removed = old
+            .into_keys()
+            .filter(|key| !new.contains_key(key))
+            .collect();
+
+        Self {
+            kind,
+            skipped: false,
+            added,
+            changed,
+            removed,
+        }
+    }
+}
+
+impl fmt::Display for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.skipped {
+            return write!(f, "{}: unchanged", self.kind);
+        }
+
+        write!(
+            f,
+            "{}: {} added, {} changed, {} removed",
+            self.kind,
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len(),
+        )?;
+
+        for (sign, keys) in [('+', &self.added), ('~', &self.changed), ('-', &self.removed)] {
+            for key in keys.iter().take(MAX_LISTED) {
+                write!(f, "\n  {sign} {key}")?;
+            }
+
+            if keys.len() > MAX_LISTED {
+                write!(f, "\n  {sign} ...and {} more", keys.len() - MAX_LISTED)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{fixture::Server, http::SurfClient};
+
+    fn keys(keys: &[String]) -> Vec<&str> {
+        keys.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn changes_between() {
+        let old = [("Torch", 3), ("Torch", 1), ("Wood", 1), ("Gel", 1)];
+        let new = [("Torch", 3), ("Torch", 2), ("Wood", 1), ("Bottle", 2)];
+
+        let changes = Changes::between("recipes", &old, &new, |(name, _)| name.to_string());
+
+        assert!(!changes.skipped);
+        assert_eq!(keys(&changes.added), ["Bottle"]);
+        assert_eq!(keys(&changes.changed), ["Torch"]);
+        assert_eq!(keys(&changes.removed), ["Gel"]);
+
+        assert_eq!(
+            changes.to_string(),
+            "recipes: 1 added, 1 changed, 1 removed\n  + Bottle\n  ~ Torch\n  - Gel"
+        );
+    }
+
+    #[test]
+    fn long_changes_are_summarized() {
+        let new = (0..MAX_LISTED + 5)
+            .map(|i| format!("{i:02}"))
+            .collect::<Vec<_>>();
+        let changes = Changes::between("items", &[], &new, Clone::clone);
+
+        let summary = changes.to_string();
+        assert!(summary.starts_with("items: 25 added, 0 changed, 0 removed\n  + 00\n"));
+        assert!(summary.ends_with("\n  + 19\n  + ...and 5 more"));
+
+        assert_eq!(Changes::skipped("images").to_string(), "images: unchanged");
+    }
+
+    #[test]
+    fn needs_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        std::fs::write(dir.path().join("input"), "a").unwrap();
+
+        let mut sync = Sync {
+            work_dir: dir.path(),
+            state: State::default(),
+        };
+
+        let hash = sync
+            .needs_run("step", &["input"], &[&output])
+            .unwrap()
+            .unwrap();
+        sync.ran("step", hash).unwrap();
+
+        // The output is still missing.
+        assert_eq!(sync.needs_run("step", &["input"], &[&output]).unwrap(), Some(hash));
+
+        std::fs::write(&output, "").unwrap();
+        assert_eq!(sync.needs_run("step", &["input"], &[&output]).unwrap(), None);
+
+        // Another step with the same inputs hasn't run yet.
+        assert!(
+            sync.needs_run("other", &["input"], &[&output])
+                .unwrap()
+                .is_some()
+        );
+
+        std::fs::write(dir.path().join("input"), "b").unwrap();
+        let changed = sync.needs_run("step", &["input"], &[&output]).unwrap();
+        assert!(changed.is_some_and(|changed| changed != hash));
+
+        // The state is saved, so the next run picks it up.
+        let saved =
+            serde_json::from_slice::<State>(&std::fs::read(dir.path().join(STATE)).unwrap())
+                .unwrap();
+        assert_eq!(saved.inputs.get("step"), Some(&hash));
+
+        assert!(sync.needs_run("step", &["missing"], &[&output]).is_err());
+    }
+
+    #[async_std::test]
+    async fn run_against_fixtures() {
+        let server = Server::start(
+            PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures")),
+            "127.0.0.1:0",
+        )
+        .unwrap();
+        let source = Source {
+            api: server.url().join("api.php").unwrap().to_string(),
+            ..Source::default()
+        };
+        let client = SurfClient::new(&source.user_agent);
+
+        let work_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+
+        let summary = run(&client, &source, work_dir.path(), output_dir.path())
+            .await
+            .unwrap();
+        let summary = summary
+            .iter()
+            .map(|c| (c.kind, keys(&c.added)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("images", vec!["Copper Pickaxe.png", "Wood.png"]),
+                ("items", vec!["Copper Pickaxe", "Wood"]),
+                (
+                    "recipes",
+                    vec![
+                        "Copper Bar",
+                        "Copper Pickaxe",
+                        "Lesser Healing Potion",
+                        "Torch",
+                        "Work Bench"
+                    ]
+                ),
+            ]
+        );
+
+        let items = steps::read_archive::<Item>(&output_dir.path().join("items.rkyv")).unwrap();
+        assert!(items.iter().all(|item| item.image_location.is_some()));
+
+        // Nothing changed on the wiki, so the second run skips every step.
+        let summary = run(&client, &source, work_dir.path(), output_dir.path())
+            .await
+            .unwrap();
+        assert!(summary.iter().all(|changes| changes.skipped));
+    }
+}
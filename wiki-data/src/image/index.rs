@@ -0,0 +1,35 @@
+use std::{collections::HashMap, ops::Deref};
+
+use rkyv::vec::ArchivedVec;
+
+use super::ArchivedImage;
+
+/// All embedded images, with constant-time lookup by file name.
+pub struct ImageIndex {
+    images: &'static ArchivedVec<ArchivedImage>,
+    by_name: HashMap<&'static str, usize>,
+}
+
+impl ImageIndex {
+    pub fn new(images: &'static ArchivedVec<ArchivedImage>) -> Self {
+        let mut by_name = HashMap::with_capacity(images.len());
+
+        for (i, image) in images.iter().enumerate() {
+            by_name.entry(image.name.as_str()).or_insert(i);
+        }
+
+        Self { images, by_name }
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&'static ArchivedImage> {
+        self.by_name.get(name).map(|&i| &self.images[i])
+    }
+}
+
+impl Deref for ImageIndex {
+    type Target = [ArchivedImage];
+
+    fn deref(&self) -> &Self::Target {
+        self.images
+    }
+}
@@ -1,6 +1,11 @@
+use rkyv::Archive;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+mod index;
+
+pub use index::ImageIndex;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Image {
     pub name: String,
     pub data: Vec<u8>,
@@ -8,15 +13,7 @@ pub struct Image {
     pub height: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ImageRef<'a> {
-    pub name: &'a str,
-    pub data: &'a [u8],
-    pub width: i32,
-    pub height: i32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct ImageLocation {
     pub name: String,
     pub url: String,
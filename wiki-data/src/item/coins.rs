@@ -0,0 +1,271 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Sub},
+};
+
+use rkyv::Archive;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use super::utils::parse_leading_number;
+
+/// An amount of money, like a buy or sell price. Stored as a number of copper coins, and split
+/// into platinum, gold, silver and copper for display.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[serde(transparent)]
+pub struct Coins {
+    copper: u64,
+}
+
+impl Coins {
+    pub const ZERO: Self = Self { copper: 0 };
+
+    pub const SILVER: u64 = 100;
+    pub const GOLD: u64 = 100 * Self::SILVER;
+    pub const PLATINUM: u64 = 100 * Self::GOLD;
+
+    pub const fn from_copper(copper: u64) -> Self {
+        Self { copper }
+    }
+
+    pub const fn new(platinum: u64, gold: u64, silver: u64, copper: u64) -> Self {
+        Self {
+            copper: platinum * Self::PLATINUM + gold * Self::GOLD + silver * Self::SILVER + copper,
+        }
+    }
+
+    /// The whole amount in copper coins.
+    pub const fn as_copper(self) -> u64 {
+        self.copper
+    }
+
+    pub const fn platinum(self) -> u64 {
+        self.copper / Self::PLATINUM
+    }
+
+    pub const fn gold(self) -> u64 {
+        self.copper / Self::GOLD % 100
+    }
+
+    pub const fn silver(self) -> u64 {
+        self.copper / Self::SILVER % 100
+    }
+
+    pub const fn copper(self) -> u64 {
+        self.copper % 100
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.copper == 0
+    }
+
+    /// Parses the first coin amount in a wiki field. Understands the HTML the coin template
+    /// expands to, as well as the template's own shorthand like "1gc 20sc". Returns `None` for
+    /// "No value" and for prices in other currencies, like Defender Medals.
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::parse_html(s).or_else(|| Self::parse_shorthand(s))
+    }
+
+    fn parse_html(s: &str) -> Option<Self> {
+        let html = Html::parse_fragment(s);
+        let coin = html.select(&Selector::parse("span.coin").unwrap()).next()?;
+
+        if let Some(value) = coin.attr("data-sort-value")
+            && let Ok(copper) = value.parse()
+        {
+            return Some(Self::from_copper(copper));
+        }
+
+        let mut coins = Self::ZERO;
+        for part in coin.select(&Selector::parse("span").unwrap()) {
+            let amount = parse_leading_number::<u64>(&part.text().collect::<String>()).ok()?;
+            coins += match part.attr("class")? {
+                "pc" => Self::new(amount, 0, 0, 0),
+                "gc" => Self::new(0, amount, 0, 0),
+                "sc" => Self::new(0, 0, amount, 0),
+                "cc" => Self::new(0, 0, 0, amount),
+                _ => continue,
+            };
+        }
+
+        Some(coins)
+    }
+
+    fn parse_shorthand(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix("{{")
+            .and_then(|s| s.strip_suffix("}}"))
+            .and_then(|s| s.split_once('|'))
+            .map_or(s, |(_, args)| args);
+
+        let mut coins = Self::ZERO;
+        let mut parts = 0;
+
+        for part in s.split_whitespace() {
+            let part = part.to_ascii_lowercase();
+            let unit = |unit| part.strip_suffix(unit)?.parse().ok();
+
+            coins += if let Some(amount) = unit("pc") {
+                Self::new(amount, 0, 0, 0)
+            } else if let Some(amount) = unit("gc") {
+                Self::new(0, amount, 0, 0)
+            } else if let Some(amount) = unit("sc") {
+                Self::new(0, 0, amount, 0)
+            } else if let Some(amount) = unit("cc") {
+                Self::new(0, 0, 0, amount)
+            } else {
+                return None;
+            };
+            parts += 1;
+        }
+
+        (parts > 0).then_some(coins)
+    }
+}
+
+impl From<&ArchivedCoins> for Coins {
+    fn from(coins: &ArchivedCoins) -> Self {
+        Self::from_copper(coins.copper.to_native())
+    }
+}
+
+impl Add for Coins {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::from_copper(self.copper + rhs.copper)
+    }
+}
+
+impl AddAssign for Coins {
+    fn add_assign(&mut self, rhs: Self) {
+        self.copper += rhs.copper;
+    }
+}
+
+/// Saturates at zero, since there are no negative prices.
+impl Sub for Coins {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_copper(self.copper.saturating_sub(rhs.copper))
+    }
+}
+
+impl Mul<u64> for Coins {
+    type Output = Self;
+
+    fn mul(self, rhs: u64) -> Self {
+        Self::from_copper(self.copper * rhs)
+    }
+}
+
+impl Sum for Coins {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+/// Formats like the wiki does, e.g. "1 GC 20 SC".
+impl fmt::Display for Coins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0 CC");
+        }
+
+        let parts = [
+            (self.platinum(), "PC"),
+            (self.gold(), "GC"),
+            (self.silver(), "SC"),
+            (self.copper(), "CC"),
+        ];
+
+        let mut first = true;
+        for (amount, unit) in parts {
+            if amount > 0 {
+                if !first {
+                    write!(f, " ")?;
+                }
+                write!(f, "{amount} {unit}")?;
+                first = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Coins::parse(
+                r#"<span class="coin" title="1 Gold&#32;20 Silver&#32;Coins" data-sort-value="12000"><span class="gc">1<i> GC</i></span><span class="sc">20<i> SC</i></span></span>"#
+            ),
+            Some(Coins::new(0, 1, 20, 0)),
+        );
+
+        assert_eq!(
+            Coins::parse(
+                r#"<span class="coin"><span class="pc">1<i> PC</i></span><span class="gc">50<i> GC</i></span></span>"#
+            ),
+            Some(Coins::new(1, 50, 0, 0)),
+        );
+
+        assert_eq!(Coins::parse("{{coin|1gc 20sc}}"), Some(Coins::new(0, 1, 20, 0)));
+        assert_eq!(Coins::parse("3 SC"), None);
+        assert_eq!(Coins::parse("3sc"), Some(Coins::new(0, 0, 3, 0)));
+
+        assert_eq!(Coins::parse("2gc 5"), None);
+        assert_eq!(Coins::parse("–"), None);
+        assert_eq!(Coins::parse("5 €"), None);
+        assert_eq!(Coins::parse("{{coin|1gc 2sç}}"), None);
+
+        assert_eq!(Coins::parse("No value"), None);
+        assert_eq!(
+            Coins::parse(
+                r#"<span data-info="600000" class="coins" title="60 Defender Medals"><s>600000*</s>60</span>"#
+            ),
+            None,
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let price = Coins::new(0, 1, 20, 0);
+
+        assert_eq!(price * 3, Coins::new(0, 3, 60, 0));
+        assert_eq!(price + Coins::new(0, 0, 80, 0), Coins::new(0, 2, 0, 0));
+        assert_eq!(price - Coins::new(0, 5, 0, 0), Coins::ZERO);
+        assert_eq!([price, price].into_iter().sum::<Coins>(), Coins::new(0, 2, 40, 0));
+
+        assert_eq!(Coins::new(0, 0, 150, 0), Coins::new(0, 1, 50, 0));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Coins::new(0, 1, 20, 0).to_string(), "1 GC 20 SC");
+        assert_eq!(Coins::new(1, 0, 0, 5).to_string(), "1 PC 5 CC");
+        assert_eq!(Coins::ZERO.to_string(), "0 CC");
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::Item;
+
+/// Everything that went wrong while parsing an item. Items are only rejected when a field they
+/// can't do without failed; otherwise the error holds the item, parsed without the failed fields.
+#[derive(Debug, Serialize)]
+pub struct ParseError {
+    pub name: String,
+    pub item_id: Option<i32>,
+    pub rejected: bool,
+    pub fields: Vec<FieldError>,
+    #[serde(skip)]
+    pub item: Option<Box<Item>>,
+}
+
+/// One field that couldn't be parsed.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    /// The value as the wiki returned it, or `None` if it was missing.
+    pub value: Option<String>,
+    pub reason: String,
+}
+
+impl ParseError {
+    /// The partially parsed item, unless it was rejected.
+    pub fn into_item(self) -> Option<Item> {
+        self.item.map(|item| *item)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(item_id) = self.item_id {
+            write!(f, " (id {item_id})")?;
+        }
+        if self.rejected {
+            write!(f, " was rejected")?;
+        }
+        write!(f, ": ")?;
+
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{field}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} {value:?}: {}", self.field, self.reason),
+            None => write!(f, "{}: {}", self.field, self.reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
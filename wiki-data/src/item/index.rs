@@ -0,0 +1,128 @@
+use std::{collections::HashMap, ops::Deref};
+
+use rkyv::vec::ArchivedVec;
+
+use super::ArchivedItem;
+
+/// All items, with constant-time lookups by id, by name and by internal name.
+pub struct ItemIndex {
+    items: &'static ArchivedVec<ArchivedItem>,
+    by_id: HashMap<i32, usize>,
+    by_name: HashMap<&'static str, usize>,
+    by_internal_name: HashMap<&'static str, usize>,
+}
+
+impl ItemIndex {
+    pub fn new(items: &'static ArchivedVec<ArchivedItem>) -> Self {
+        let mut by_id = HashMap::with_capacity(items.len());
+        let mut by_name = HashMap::with_capacity(items.len());
+        let mut by_internal_name = HashMap::with_capacity(items.len());
+
+        for (i, item) in items.iter().enumerate() {
+            by_id.entry(item.item_id.to_native()).or_insert(i);
+            by_name.entry(item.name.as_str()).or_insert(i);
+            if !item.internal_name.is_empty() {
+                by_internal_name
+                    .entry(item.internal_name.as_str())
+                    .or_insert(i);
+            }
+        }
+
+        Self {
+            items,
+            by_id,
+            by_name,
+            by_internal_name,
+        }
+    }
+
+    pub fn by_id(&self, item_id: i32) -> Option<&'static ArchivedItem> {
+        self.by_id.get(&item_id).map(|&i| &self.items[i])
+    }
+
+    /// Looks up an item by its display name, like "Copper Pickaxe".
+    pub fn by_name(&self, name: &str) -> Option<&'static ArchivedItem> {
+        self.by_name.get(name).map(|&i| &self.items[i])
+    }
+
+    /// Looks up an item by its internal name, like "CopperPickaxe".
+    pub fn by_internal_name(&self, internal_name: &str) -> Option<&'static ArchivedItem> {
+        self.by_internal_name
+            .get(internal_name)
+            .map(|&i| &self.items[i])
+    }
+}
+
+impl Deref for ItemIndex {
+    type Target = [ArchivedItem];
+
+    fn deref(&self) -> &Self::Target {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rkyv::rancor;
+
+    use super::*;
+    use crate::item::{Item, RawItem};
+
+    fn index(json: &[&str]) -> ItemIndex {
+        let items = json
+            .iter()
+            .map(|json| serde_json::from_str::<RawItem>(json).unwrap())
+            .map(|raw| Item::from_raw(&raw, None).unwrap())
+            .collect::<Vec<_>>();
+
+        let bytes = rkyv::to_bytes::<rancor::Error>(&items).unwrap().leak();
+        ItemIndex::new(rkyv::access::<_, rancor::Error>(bytes).unwrap())
+    }
+
+    #[test]
+    fn internal_names() {
+        let items = index(&[
+            r#"{"itemid": "3509", "name": "Copper Pickaxe", "internalname": "CopperPickaxe",
+                "rare": "0"}"#,
+            r#"{"itemid": "20", "name": "Copper Bar", "internalname": "CopperBar", "rare": "0"}"#,
+            r#"{"itemid": "9", "name": "Wood", "internalname": "", "rare": "0"}"#,
+        ]);
+
+        assert_eq!(items.by_internal_name("CopperPickaxe").unwrap().name, "Copper Pickaxe");
+        assert_eq!(items.by_internal_name("CopperBar").unwrap().item_id, 20);
+        assert!(items.by_internal_name("Copper Pickaxe").is_none());
+        assert!(items.by_internal_name("").is_none());
+        assert_eq!(items.by_name("Wood").unwrap().item_id, 9);
+    }
+
+    #[cfg(feature = "items")]
+    #[test]
+    fn lookups_match_scan() {
+        use crate::ITEMS;
+
+        for item in ITEMS.iter().step_by(97) {
+            let found = ITEMS.by_name(&item.name).unwrap();
+            assert_eq!(found.name, item.name);
+
+            let found = ITEMS.by_id(item.item_id.to_native()).unwrap();
+            assert_eq!(found.item_id, item.item_id);
+
+            if !item.internal_name.is_empty() {
+                let found = ITEMS.by_internal_name(&item.internal_name).unwrap();
+                assert_eq!(found.internal_name, item.internal_name);
+            }
+        }
+
+        assert!(ITEMS.by_name("Not An Item").is_none());
+    }
+
+    #[cfg(feature = "items")]
+    #[test]
+    #[ignore = "items.rkyv predates internal names; regenerate it with wiki-data-cli"]
+    fn shipped_internal_names() {
+        use crate::ITEMS;
+
+        let pickaxe = ITEMS.by_internal_name("CopperPickaxe").unwrap();
+        assert_eq!(pickaxe.name, "Copper Pickaxe");
+    }
+}
@@ -1,15 +1,16 @@
+use rkyv::Archive;
 use serde::{Deserialize, Serialize};
 
 use crate::ImageLocation;
 
 use super::{
-    WikiText,
+    Coins, FieldError, ParseError, WikiText,
     raw::RawItem,
     types::{DamageType, ItemType, Rarity},
-    utils::{parse_leading_number, parse_opt_leading_number, parse_rarity},
+    utils::{parse_bonus, parse_leading_number, parse_power, parse_rarity},
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Item {
     pub item_id: i32,
     pub name: String,
@@ -26,45 +27,246 @@ pub struct Item {
     pub mana: Option<i32>,
     pub hheal: Option<i32>,
     pub mheal: Option<i32>,
-    pub buy: Option<String>,
-    pub sell: Option<String>,
+    pub buy: Option<Coins>,
+    pub sell: Option<Coins>,
     pub stack: Option<i32>,
+    /// How many of the item have to be researched in Journey mode to unlock duplicating it.
+    pub research: Option<u32>,
     pub consumable: Option<bool>,
     pub hardmode: Option<bool>,
     pub rarity: Rarity,
     pub tooltip: Option<WikiText>,
+    /// Pickaxe power, in percent.
+    pub pick: Option<u32>,
+    /// Axe power, in percent, as shown in game (five times the internal value).
+    pub axe: Option<u32>,
+    /// Hammer power, in percent.
+    pub hammer: Option<u32>,
+    /// Fishing power, in percent.
+    pub fishing: Option<u32>,
+    /// Bait power, in percent.
+    pub bait: Option<u32>,
+    /// How many frames a tool takes between hits, separately from its use time.
+    pub toolspeed: Option<u32>,
+    /// Extra tiles of reach, like the +1 of most pickaxes. Can be negative.
+    pub bonus: Option<i32>,
+    #[serde(default)]
+    pub internal_name: String,
+}
+
+impl ArchivedItem {
+    pub fn rarity(&self) -> Rarity {
+        Rarity::try_from(self.rarity.to_native()).unwrap_or(Rarity::White)
+    }
+
+    /// The tool powers this item has, like `(55, "pickaxe")`, strongest first for drills and
+    /// other multi-tools that have several.
+    pub fn powers(&self) -> Vec<(u32, &'static str)> {
+        let mut powers = [
+            (&self.pick, "pickaxe"),
+            (&self.axe, "axe"),
+            (&self.hammer, "hammer"),
+            (&self.fishing, "fishing"),
+            (&self.bait, "bait"),
+        ]
+        .into_iter()
+        .filter_map(|(power, kind)| Some((power.as_ref()?.to_native(), kind)))
+        .collect::<Vec<_>>();
+
+        powers.sort_by_key(|&(power, _)| std::cmp::Reverse(power));
+        powers
+    }
 }
 
 impl Item {
-    pub fn from_raw(item: &RawItem, image_location: Option<ImageLocation>) -> Option<Self> {
-        Some(Self {
-            item_id: item.itemid()?,
+    /// Parses an item, collecting every field that fails instead of stopping at the first one.
+    /// Missing or empty fields aren't errors, except for the id and rarity, without which the
+    /// item is rejected.
+    pub fn from_raw(
+        item: &RawItem,
+        image_location: Option<ImageLocation>,
+    ) -> Result<Self, ParseError> {
+        let mut fields = Fields { raw: item, errors: Vec::new() };
+
+        let item_id = fields.required("itemid", item.itemid(), "not an integer");
+        let rarity = fields.required("rare", parse_rarity(&item.rare()), "not a rarity");
+
+        let parsed = Self {
+            item_id: item_id.unwrap_or_default(),
             name: item.name().to_owned(),
             image_location,
-            types: item.r#type(),
-            damage: parse_opt_leading_number(&item.damage()),
-            damage_type: item
-                .damagetype()
-                .map(|s| s.parse().expect("invalid damage type")),
-            defense: parse_opt_leading_number(&item.defense()),
+            types: ItemType::classify(item),
+            damage: fields.optional("damage", item.damage(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
+            damage_type: fields.optional(
+                "damagetype",
+                item.damagetype(),
+                "unknown damage type",
+                |s| s.parse().ok(),
+            ),
+            defense: fields.optional("defense", item.defense(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
             autoswing: item.autoswing(),
-            velocity: parse_opt_leading_number(&item.velocity()),
-            knockback: item
-                .knockback()
-                .as_ref()
-                .and_then(|s| parse_leading_number(s).ok()),
-            critical: parse_opt_leading_number(&item.critical()),
-            usetime: parse_opt_leading_number(&item.usetime()),
-            mana: parse_opt_leading_number(&item.mana()),
-            hheal: parse_opt_leading_number(&item.hheal()),
-            mheal: parse_opt_leading_number(&item.mheal()),
-            buy: item.buy(),
-            sell: item.sell(),
-            stack: parse_opt_leading_number(&item.stack()),
+            velocity: fields.optional("velocity", item.velocity(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
+            knockback: fields.optional("knockback", item.knockback(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
+            critical: fields.optional("critical", item.critical(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
+            usetime: fields.optional("usetime", item.usetime(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
+            mana: fields
+                .optional("mana", item.mana(), "not a number", |s| parse_leading_number(s).ok()),
+            hheal: fields
+                .optional("hheal", item.hheal(), "not a number", |s| parse_leading_number(s).ok()),
+            mheal: fields
+                .optional("mheal", item.mheal(), "not a number", |s| parse_leading_number(s).ok()),
+            buy: fields.price("buy", item.buy()),
+            sell: fields.price("sell", item.sell()),
+            stack: fields
+                .optional("stack", item.stack(), "not a number", |s| parse_leading_number(s).ok()),
+            research: fields.optional("research", item.research(), "not a number", |s| {
+                parse_leading_number(s).ok()
+            }),
             consumable: item.consumable(),
             hardmode: item.hardmode(),
-            rarity: parse_rarity(&item.rare())?,
+            rarity: rarity.unwrap_or(Rarity::White),
             tooltip: item.tooltip().map(|s| WikiText::new(&s)),
+            pick: fields.power("pick", item.pick()),
+            axe: fields.power("axe", item.axe()),
+            hammer: fields.power("hammer", item.hammer()),
+            fishing: fields.power("fishing", item.fishing()),
+            bait: fields
+                .optional("bait", item.bait(), "not a positive integer", |s| s.parse().ok()),
+            toolspeed: fields.power("toolspeed", item.toolspeed()),
+            bonus: fields.optional("bonus", item.bonus(), "not a range bonus", parse_bonus),
+            internal_name: item.internalname().to_owned(),
+        };
+
+        if fields.errors.is_empty() {
+            return Ok(parsed);
+        }
+
+        let rejected = item_id.is_none() || rarity.is_none();
+
+        Err(ParseError {
+            name: parsed.name.clone(),
+            item_id,
+            rejected,
+            fields: fields.errors,
+            item: (!rejected).then(|| Box::new(parsed)),
         })
     }
 }
+
+/// Parses the fields of one item, remembering the ones that failed.
+struct Fields<'a> {
+    raw: &'a RawItem,
+    errors: Vec<FieldError>,
+}
+
+impl Fields<'_> {
+    fn required<T>(&mut self, field: &'static str, value: Option<T>, reason: &str) -> Option<T> {
+        if value.is_none() {
+            let raw = self.raw.raw_field(field);
+            let reason = if raw.is_some() { reason } else { "missing" };
+            self.fail(field, raw, reason);
+        }
+
+        value
+    }
+
+    fn optional<T>(
+        &mut self,
+        field: &'static str,
+        value: Option<String>,
+        reason: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Option<T> {
+        let value = value.filter(|s| !s.trim().is_empty())?;
+
+        let parsed = parse(&value);
+        if parsed.is_none() {
+            self.fail(field, Some(value), reason);
+        }
+
+        parsed
+    }
+
+    fn price(&mut self, field: &'static str, value: Option<String>) -> Option<Coins> {
+        let value = value.filter(|s| s.trim() != "No value")?;
+        self.optional(field, Some(value), "not a coin amount", Coins::parse)
+    }
+
+    /// Like [`Self::optional`], but zero means the item doesn't have the power at all.
+    fn power(&mut self, field: &'static str, value: Option<String>) -> Option<u32> {
+        self.optional(field, value, "not a power", parse_power)
+            .filter(|&power| power > 0)
+    }
+
+    fn fail(&mut self, field: &'static str, value: Option<String>, reason: &str) {
+        self.errors.push(FieldError {
+            field,
+            value,
+            reason: reason.to_owned(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(json: &str) -> RawItem {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn partially_parsed() {
+        let item =
+            raw(r#"{"itemid": "3509", "name": "Copper Pickaxe", "internalname": "CopperPickaxe",
+                "rare": "0", "damage": "4", "damagetype": "Thrown", "sell": "No value",
+                "pick": "lots", "axe": "0"}"#);
+
+        let error = Item::from_raw(&item, None).unwrap_err();
+        assert!(!error.rejected);
+
+        let fields = error.fields.iter().map(|f| f.field).collect::<Vec<_>>();
+        assert_eq!(fields, ["damagetype", "pick"]);
+        assert_eq!(error.fields[0].value.as_deref(), Some("Thrown"));
+
+        let item = error.into_item().unwrap();
+        assert_eq!(item.damage, Some(4));
+        assert_eq!(item.damage_type, None);
+        assert_eq!(item.axe, None);
+    }
+
+    #[test]
+    fn bait() {
+        let item = raw(r#"{"itemid": "2002", "name": "Worm", "internalname": "Worm", "rare": "1",
+                "bait": "25"}"#);
+        assert_eq!(Item::from_raw(&item, None).unwrap().bait, Some(25));
+
+        let item = raw(r#"{"itemid": "2002", "name": "Worm", "internalname": "Worm", "rare": "1",
+                "bait": "-5"}"#);
+        let error = Item::from_raw(&item, None).unwrap_err();
+        assert_eq!(error.fields[0].field, "bait");
+        assert_eq!(error.into_item().unwrap().bait, None);
+    }
+
+    #[test]
+    fn rejected() {
+        let item =
+            raw(r#"{"itemid": "abc", "name": "Copper Pickaxe", "internalname": "CopperPickaxe"}"#);
+
+        let error = Item::from_raw(&item, None).unwrap_err();
+        assert!(error.rejected);
+        assert!(error.into_item().is_none());
+    }
+}
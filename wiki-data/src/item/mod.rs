@@ -1,10 +1,16 @@
+mod coins;
+mod error;
+mod index;
 mod item;
 mod raw;
 mod types;
 mod utils;
 mod wikitext;
 
-pub use item::Item;
+pub use coins::{ArchivedCoins, Coins};
+pub use error::{FieldError, ParseError};
+pub use index::ItemIndex;
+pub use item::{ArchivedItem, Item};
 pub use raw::RawItem;
 pub use types::*;
-pub use wikitext::WikiText;
+pub use wikitext::{ArchivedSpan, ArchivedStyle, ArchivedWikiText, Span, Style, WikiText};
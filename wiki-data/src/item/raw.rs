@@ -3,8 +3,6 @@ use std::str::FromStr;
 use scraper::Html;
 use serde::{Deserialize, Serialize};
 
-use super::types::ItemType;
-
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct RawItem {
     itemid: Option<String>,        // Integer
@@ -84,6 +82,17 @@ impl RawItem {
     //     &self.name
     // }
 
+    /// The unparsed value of a field, as the wiki returned it, looked up by name. This goes
+    /// through serde, so it's meant for reporting fields that failed to parse; the getters below
+    /// are much cheaper.
+    pub fn raw_field(&self, field: &str) -> Option<String> {
+        serde_json::to_value(self)
+            .ok()?
+            .get(field)?
+            .as_str()
+            .map(str::to_owned)
+    }
+
     pub fn internalname(&self) -> &str {
         &self.internalname
     }
@@ -107,7 +116,7 @@ impl RawItem {
 
     getters! {
         list (Vec):
-            r#type: ItemType, listcat: String, tag: String, buffs: String,
+            r#type: String, listcat: String, tag: String, buffs: String,
             debuffs: String,
     }
 
@@ -117,7 +126,7 @@ impl RawItem {
 
     getters! {
         integer (Option):
-            itemid: i32, bait: i32, placedwidth: i32, placedheight: i32,
+            itemid: i32, placedwidth: i32, placedheight: i32,
     }
 
     fn boolean(field: &Option<String>) -> Option<bool> {
@@ -151,6 +160,6 @@ impl RawItem {
 
     getters! {
         string (Option):
-            damagetype: String, bodyslot: String,
+            damagetype: String, bodyslot: String, bait: String,
     }
 }
@@ -1,30 +1,196 @@
 use core::fmt::{Display, Formatter};
-use std::str::FromStr;
+use std::{convert::Infallible, str::FromStr};
 
 use derive_more::derive::TryFrom;
+use rkyv::{
+    Archive, Place,
+    primitive::ArchivedI32,
+    rancor::{Fallible, Panic, Source},
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+use super::raw::RawItem;
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
 pub enum ItemType {
     Weapon,
     Tool,
+    Accessory,
+    Armor(Option<ArmorSlot>),
+    Vanity,
+    Ammo,
+    Potion,
+    Food,
+    Consumable,
+    Material,
+    Placeable,
+    Block,
+    Wall,
+    Furniture,
+    LightSource,
+    Mechanism,
+    Mount,
+    Minecart,
+    Pet,
+    LightPet,
+    Hook,
+    Wings,
+    Dye,
+    Bait,
+    Fish,
+    BossSummon,
+    TreasureBag,
+    /// A type the wiki uses that isn't known here, kept as it was written.
+    Other(String),
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum ArmorSlot {
+    Head,
+    Body,
+    Legs,
+}
+
+impl ItemType {
+    /// Recognizes the names used in the `type`, `listcat` and `tag` fields of the wiki, which
+    /// differ in case and sometimes in wording.
+    fn known(s: &str) -> Option<Self> {
+        Some(match s.trim().to_lowercase().as_str() {
+            "weapon" => Self::Weapon,
+            "tool" | "pickaxe" | "axe" | "hammer" | "hamaxe" | "drill" | "chainsaw"
+            | "jackhammer" | "fishing pole" | "fishing rod" => Self::Tool,
+            "accessory" => Self::Accessory,
+            "armor" | "armour" | "armor piece" => Self::Armor(None),
+            "helmet" | "head armor" => Self::Armor(Some(ArmorSlot::Head)),
+            "chestplate" | "breastplate" | "body armor" => Self::Armor(Some(ArmorSlot::Body)),
+            "greaves" | "leggings" | "leg armor" => Self::Armor(Some(ArmorSlot::Legs)),
+            "vanity" | "vanity item" | "social" => Self::Vanity,
+            "ammunition" | "ammo" => Self::Ammo,
+            "potion" | "buff potion" | "healing potion" | "mana potion" => Self::Potion,
+            "food" => Self::Food,
+            "consumable" => Self::Consumable,
+            "crafting material" | "material" | "crafting ingredient" => Self::Material,
+            "placeable" => Self::Placeable,
+            "block" | "brick" => Self::Block,
+            "wall" | "background wall" => Self::Wall,
+            "furniture" | "crafting station" => Self::Furniture,
+            "light source" | "light" | "torch" => Self::LightSource,
+            "mechanism" | "wiring" => Self::Mechanism,
+            "mount" | "mount summon" => Self::Mount,
+            "minecart" => Self::Minecart,
+            "pet" | "pet summon" => Self::Pet,
+            "light pet" | "lightpet" | "light pet summon" => Self::LightPet,
+            "hook" | "grappling hook" | "grapple" => Self::Hook,
+            "wings" => Self::Wings,
+            "dye" | "hair dye" => Self::Dye,
+            "bait" => Self::Bait,
+            "fish" | "quest fish" => Self::Fish,
+            "boss summon" | "boss summoning item" | "event summon" => Self::BossSummon,
+            "treasure bag" | "grab bag" | "crate" => Self::TreasureBag,
+            _ => return None,
+        })
+    }
+
+    /// Works out all types of an item from the fields of the wiki that describe it. Types from
+    /// the `type` field are always kept, even when they aren't known, while `listcat` and `tag`
+    /// only add types that are. The order is that of the fields, without duplicates.
+    pub fn classify(item: &RawItem) -> Vec<Self> {
+        let mut types = Vec::new();
+
+        let listed = item.r#type().into_iter().map(|s| s.parse().unwrap());
+        let categories = item
+            .listcat()
+            .into_iter()
+            .chain(item.tag())
+            .filter_map(|s| Self::known(&s));
+
+        for ty in listed.chain(categories) {
+            push_type(&mut types, ty);
+        }
+
+        // Vanity items have a body slot too, but aren't armor.
+        if let Some(slot) = item.bodyslot().as_deref().and_then(ArmorSlot::parse)
+            && !types.contains(&Self::Vanity)
+        {
+            push_type(&mut types, Self::Armor(Some(slot)));
+        }
+
+        if item.placeable() == Some(true) {
+            push_type(&mut types, Self::Placeable);
+        }
+
+        types
+    }
+}
+
+/// Adds `ty` unless it's already there. There's only ever one armor type, which takes the first
+/// slot that's known.
+fn push_type(types: &mut Vec<ItemType>, ty: ItemType) {
+    if let ItemType::Armor(new) = ty
+        && let Some(ItemType::Armor(slot)) =
+            types.iter_mut().find(|t| matches!(t, ItemType::Armor(_)))
+    {
+        *slot = slot.or(new);
+    } else if !types.contains(&ty) {
+        types.push(ty);
+    }
+}
+
+impl ArmorSlot {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.to_lowercase();
+
+        if s.contains("head") || s.contains("helmet") {
+            Some(Self::Head)
+        } else if s.contains("body") || s.contains("chest") || s.contains("shirt") {
+            Some(Self::Body)
+        } else if s.contains("leg") || s.contains("greaves") || s.contains("pants") {
+            Some(Self::Legs)
+        } else {
+            None
+        }
+    }
 }
 
 impl FromStr for ItemType {
-    type Err = ();
+    type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "weapon" => Ok(ItemType::Weapon),
-            "tool" => Ok(ItemType::Tool),
-            _ => Err(()),
-        }
+        Ok(Self::known(s).unwrap_or_else(|| Self::Other(s.trim().to_owned())))
     }
 }
 
 impl Display for ItemType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Self::Armor(Some(slot)) => write!(f, "{slot:?} armor"),
+            Self::Armor(None) => write!(f, "Armor"),
+            Self::LightSource => write!(f, "Light source"),
+            Self::LightPet => write!(f, "Light pet"),
+            Self::BossSummon => write!(f, "Boss summon"),
+            Self::TreasureBag => write!(f, "Treasure bag"),
+            Self::Other(s) => write!(f, "{s}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+impl Display for ArchivedItemType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        rkyv::deserialize::<ItemType, Panic>(self).unwrap().fmt(f)
     }
 }
 
@@ -73,6 +239,33 @@ impl Rarity {
     }
 }
 
+// Rarities are archived as their numeric id, since the archived enum rkyv would derive can't hold
+// the negative discriminants.
+impl Archive for Rarity {
+    type Archived = ArchivedI32;
+    type Resolver = ();
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        (*self as i32).resolve(resolver, out)
+    }
+}
+
+impl<S: Fallible + ?Sized> rkyv::Serialize<S> for Rarity {
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<D> rkyv::Deserialize<Rarity, D> for ArchivedI32
+where
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    fn deserialize(&self, _: &mut D) -> Result<Rarity, D::Error> {
+        Rarity::try_from(self.to_native()).map_err(D::Error::new)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum RarityColor {
     Static { r: u8, g: u8, b: u8 },
@@ -94,7 +287,7 @@ impl FromStr for Rarity {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub enum DamageType {
     Melee,
     Ranged,
@@ -123,3 +316,59 @@ impl Display for DamageType {
         write!(f, "{self:?}")
     }
 }
+
+impl Display for ArchivedDamageType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        rkyv::deserialize::<DamageType, Panic>(self).unwrap().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(json: &str) -> RawItem {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn classify() {
+        let pickaxe = raw(
+            r#"{"name": "Copper Pickaxe", "internalname": "CopperPickaxe", "type": "Tool^Weapon",
+                "listcat": "tool^weapon^pickaxe", "placeable": "0"}"#,
+        );
+        assert_eq!(ItemType::classify(&pickaxe), [ItemType::Tool, ItemType::Weapon]);
+
+        let wood =
+            raw(r#"{"name": "Wood", "internalname": "Wood", "type": "Crafting material^Block",
+                "listcat": "block^material", "placeable": "1"}"#);
+        assert_eq!(
+            ItemType::classify(&wood),
+            [ItemType::Material, ItemType::Block, ItemType::Placeable],
+        );
+
+        let helmet =
+            raw(r#"{"name": "Copper Helmet", "internalname": "CopperHelmet", "type": "Armor",
+                "bodyslot": "head"}"#);
+        assert_eq!(ItemType::classify(&helmet), [ItemType::Armor(Some(ArmorSlot::Head))]);
+
+        let vanity = raw(r#"{"name": "Top Hat", "internalname": "TopHat", "type": "Vanity item",
+                "bodyslot": "head"}"#);
+        assert_eq!(ItemType::classify(&vanity), [ItemType::Vanity]);
+
+        let unknown =
+            raw(r#"{"name": "Music Box", "internalname": "MusicBox", "type": "Music box",
+                "tag": "light pet^nonsense"}"#);
+        assert_eq!(
+            ItemType::classify(&unknown),
+            [ItemType::Other("Music box".to_owned()), ItemType::LightPet],
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ItemType::Armor(Some(ArmorSlot::Legs)).to_string(), "Legs armor");
+        assert_eq!(ItemType::LightPet.to_string(), "Light pet");
+        assert_eq!(ItemType::Other("Music box".to_owned()).to_string(), "Music box");
+    }
+}
@@ -1,10 +1,6 @@
 use std::str::FromStr;
 
-use super::types::Rarity;
-
-pub fn parse_opt_leading_number<T: FromStr>(s: &Option<String>) -> Option<T> {
-    s.as_ref().and_then(|s| parse_leading_number(s).ok())
-}
+use super::{WikiText, types::Rarity};
 
 pub fn parse_leading_number<T: FromStr>(s: &str) -> Result<T, T::Err> {
     s.char_indices()
@@ -24,6 +20,20 @@ pub fn parse_rarity(s: &Option<String>) -> Option<Rarity> {
         .ok()
 }
 
+/// Parses a tool power like "55%" or "35". Values that differ between versions of the game are
+/// written like "200% (Desktop) 100% (Old-gen)" on the wiki, in which case the first one is used.
+pub fn parse_power(s: &str) -> Option<u32> {
+    let text = WikiText::new(s).plain();
+    parse_leading_number(text.trim()).ok()
+}
+
+/// Parses a range bonus like "+1", "-1" or "+3 range".
+pub fn parse_bonus(s: &str) -> Option<i32> {
+    let text = WikiText::new(s).plain();
+    let text = text.trim();
+    parse_leading_number(text.strip_prefix('+').unwrap_or(text)).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +48,28 @@ mod tests {
         assert_eq!(parse_leading_number::<i32>("123 456"), Ok(123));
         assert_eq!(parse_leading_number::<f32>("123.0 456"), Ok(123.0));
     }
+
+    #[test]
+    fn power() {
+        assert_eq!(parse_power("35"), Some(35));
+        assert_eq!(parse_power("55%"), Some(55));
+        assert_eq!(parse_power(" 110% "), Some(110));
+        assert_eq!(parse_power("0"), Some(0));
+        assert_eq!(parse_power(""), None);
+        assert_eq!(
+            parse_power(
+                r#"200%<span class="eico s-desktop-console-mobile" title="Desktop, Console and Mobile versions"></span> / 100%<span class="eico s-old-gen-3ds" title="Old-gen console and 3DS versions"></span>"#
+            ),
+            Some(200),
+        );
+        assert_eq!(parse_power("<i>Varies</i>"), None);
+    }
+
+    #[test]
+    fn bonus() {
+        assert_eq!(parse_bonus("+1"), Some(1));
+        assert_eq!(parse_bonus("-1"), Some(-1));
+        assert_eq!(parse_bonus("+3 range"), Some(3));
+        assert_eq!(parse_bonus("None"), None);
+    }
 }
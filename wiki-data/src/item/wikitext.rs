@@ -1,31 +1,231 @@
-use std::ops::Deref;
-
-use scraper::{node::Node, Html};
+use rkyv::Archive;
+use scraper::{ElementRef, Html, node::Node};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Formatted text from the wiki, like an item tooltip. The HTML the wiki renders is boiled down to
+/// a flat list of spans that keeps what matters for showing it in game style: emphasis, colors,
+/// links to other pages and inline item icons.
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
 pub struct WikiText {
-    text: String,
+    pub spans: Vec<Span>,
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub enum Span {
+    Text {
+        text: String,
+        style: Style,
+    },
+    /// Text linking to another page of the wiki, by title.
+    Link {
+        text: String,
+        page: String,
+        style: Style,
+    },
+    /// An inline image, by file name, like "Wood.png".
+    Image {
+        file: String,
+    },
+    LineBreak,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub color: Option<[u8; 3]>,
 }
 
 impl WikiText {
     pub fn new(text: &str) -> Self {
-        Self {
-            text: Html::parse_fragment(&unescape(text))
-                .root_element()
-                .descendants()
-                .filter_map(|node| match node.value() {
-                    Node::Text(text) => Some(text.deref()),
-                    Node::Element(el) if el.name() == "br" => Some("\n"),
-                    _ => None,
-                })
-                .collect::<String>(),
+        let html = Html::parse_fragment(&unescape(text));
+
+        let mut spans = Vec::new();
+        walk(html.root_element(), Style::default(), None, &mut spans);
+
+        Self { spans }
+    }
+
+    pub fn plain(&self) -> String {
+        let mut out = String::new();
+
+        for span in &self.spans {
+            match span {
+                Span::Text { text, .. } | Span::Link { text, .. } => out += text,
+                Span::LineBreak => out.push('\n'),
+                Span::Image { .. } => {}
+            }
         }
+
+        out
     }
+}
 
+impl ArchivedWikiText {
     pub fn plain(&self) -> String {
-        self.text.clone()
+        let mut out = String::new();
+
+        for span in self.spans.iter() {
+            match span {
+                ArchivedSpan::Text { text, .. } | ArchivedSpan::Link { text, .. } => out += text,
+                ArchivedSpan::LineBreak => out.push('\n'),
+                ArchivedSpan::Image { .. } => {}
+            }
+        }
+
+        out
+    }
+
+    /// The spans split into lines at line breaks.
+    pub fn lines(&self) -> impl Iterator<Item = &[ArchivedSpan]> {
+        self.spans
+            .as_slice()
+            .split(|span| matches!(span, ArchivedSpan::LineBreak))
+    }
+}
+
+impl ArchivedStyle {
+    pub fn color(&self) -> Option<[u8; 3]> {
+        self.color.as_ref().copied()
+    }
+}
+
+fn walk(element: ElementRef, style: Style, link: Option<&str>, out: &mut Vec<Span>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => push_text(out, text, style, link),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let el = child.value();
+
+                let mut style = style;
+                let mut link = link;
+
+                match el.name() {
+                    "br" => {
+                        out.push(Span::LineBreak);
+                        continue;
+                    }
+                    "img" => {
+                        if let Some(file) = image_file(el.attr("alt"), el.attr("src")) {
+                            out.push(Span::Image { file });
+                        }
+                        continue;
+                    }
+                    "a" => link = el.attr("title").or_else(|| el.attr("href")).or(link),
+                    "b" | "strong" => style.bold = true,
+                    "i" | "em" => style.italic = true,
+                    "font" => style.color = el.attr("color").and_then(parse_color).or(style.color),
+                    _ => {}
+                }
+
+                if let Some(color) = el.attr("style").and_then(style_color) {
+                    style.color = Some(color);
+                }
+
+                walk(child, style, link, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds text, merging it into the previous span if that looks the same.
+fn push_text(out: &mut Vec<Span>, text: &str, style: Style, link: Option<&str>) {
+    if text.is_empty() {
+        return;
+    }
+
+    match (out.last_mut(), link) {
+        (Some(Span::Text { text: last, style: last_style }), None) if *last_style == style => {
+            last.push_str(text);
+        }
+        (Some(Span::Link { text: last, page, style: last_style }), Some(link))
+            if *last_style == style && page == &page_title(link) =>
+        {
+            last.push_str(text);
+        }
+        (_, None) => out.push(Span::Text { text: text.to_owned(), style }),
+        (_, Some(link)) => out.push(Span::Link {
+            text: text.to_owned(),
+            page: page_title(link),
+            style,
+        }),
+    }
+}
+
+/// Turns a link target like "/wiki/Iron_Bar" into a page title like "Iron Bar".
+fn page_title(link: &str) -> String {
+    let link = link.rsplit_once("/wiki/").map_or(link, |(_, title)| title);
+    link.split('#').next().unwrap_or(link).replace('_', " ")
+}
+
+/// Finds the file name of an image, preferring the alt text when it names a file, since the
+/// source of a thumbnail is something like "/images/thumb/a/ab/Wood.png/16px-Wood.png".
+fn image_file(alt: Option<&str>, src: Option<&str>) -> Option<String> {
+    if let Some(alt) = alt
+        && alt.contains('.')
+    {
+        return Some(alt.to_owned());
     }
+
+    let src = src?.split('?').next()?;
+    let mut segments = src.rsplit('/');
+    let last = segments.next()?;
+
+    let file = if src.contains("/thumb/") {
+        segments.next()?
+    } else {
+        last
+    };
+    Some(file.replace('_', " "))
+}
+
+fn style_color(style: &str) -> Option<[u8; 3]> {
+    style.split(';').find_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        (property.trim() == "color")
+            .then(|| parse_color(value))
+            .flatten()
+    })
+}
+
+/// Parses colors like "#ff8000", "#f80" and "rgb(255, 128, 0)".
+fn parse_color(s: &str) -> Option<[u8; 3]> {
+    let s = s.trim();
+
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize, len: usize| u8::from_str_radix(hex.get(i..i + len)?, 16).ok();
+
+        return match hex.len() {
+            6 => Some([channel(0, 2)?, channel(2, 2)?, channel(4, 2)?]),
+            3 => Some([channel(0, 1)? * 17, channel(1, 1)? * 17, channel(2, 1)? * 17]),
+            _ => None,
+        };
+    }
+
+    let args = s.strip_prefix("rgb(")?.strip_suffix(')')?;
+    let mut channels = args.split(',').map(|c| c.trim().parse().ok());
+
+    Some([channels.next()??, channels.next()??, channels.next()??])
 }
 
 fn unescape(text: &str) -> String {
@@ -35,3 +235,71 @@ fn unescape(text: &str) -> String {
         .replace("&quot;", "\"")
         .replace("&#39;", "'")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans() {
+        let text = WikiText::new(
+            r#"Summons a <a href="/wiki/Pets" title="Pets">pet</a><br/><span style="color: #ff8000">Legendary</span> <i>quest</i> item <span class="i"><a href="/wiki/Wood" title="Wood"><img alt="Wood.png" src="/images/thumb/a/ab/Wood.png/16px-Wood.png" /></a></span>"#,
+        );
+
+        let plain = Style::default();
+        assert_eq!(
+            text.spans,
+            [
+                Span::Text {
+                    text: "Summons a ".to_owned(),
+                    style: plain
+                },
+                Span::Link {
+                    text: "pet".to_owned(),
+                    page: "Pets".to_owned(),
+                    style: plain,
+                },
+                Span::LineBreak,
+                Span::Text {
+                    text: "Legendary".to_owned(),
+                    style: Style {
+                        color: Some([0xff, 0x80, 0x00]),
+                        ..plain
+                    },
+                },
+                Span::Text { text: " ".to_owned(), style: plain },
+                Span::Text {
+                    text: "quest".to_owned(),
+                    style: Style { italic: true, ..plain },
+                },
+                Span::Text {
+                    text: " item ".to_owned(),
+                    style: plain
+                },
+                Span::Image { file: "Wood.png".to_owned() },
+            ],
+        );
+
+        assert_eq!(text.plain(), "Summons a pet\nLegendary quest item ");
+    }
+
+    #[test]
+    fn images() {
+        assert_eq!(
+            image_file(None, Some("/images/thumb/a/ab/Iron_Bar.png/16px-Iron_Bar.png?4e2f1")),
+            Some("Iron Bar.png".to_owned()),
+        );
+        assert_eq!(
+            image_file(Some(""), Some("/images/a/ab/Wood.png")),
+            Some("Wood.png".to_owned())
+        );
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#f80"), Some([0xff, 0x88, 0x00]));
+        assert_eq!(parse_color("rgb(1, 2, 3)"), Some([1, 2, 3]));
+        assert_eq!(style_color("font-weight: bold; color:#000000"), Some([0, 0, 0]));
+        assert_eq!(parse_color("red"), None);
+    }
+}
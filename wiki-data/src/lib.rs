@@ -2,21 +2,109 @@
 
 pub mod image;
 pub mod item;
+pub mod pack;
+pub mod recipe;
 
-pub use self::image::{Image, ImageLocation, ImageRef};
-pub use self::item::Item;
+pub use self::image::{Image, ImageLocation};
+pub use self::item::{ArchivedItem, Item};
+pub use self::pack::{DataPack, Pack};
+pub use self::recipe::{ArchivedRecipe, Recipe};
 
 // use self::item::enums::{DamageType, ItemType, Rarity};
 // pub fn items() -> Vec<Item> {
 //     include!("_items.rs")
 // }
 
+/// Embedded archive bytes, aligned so they can be accessed in place.
+#[cfg(any(feature = "items", feature = "recipes", feature = "images"))]
+#[repr(C, align(16))]
+struct Aligned<B: ?Sized>(B);
+
+#[cfg(feature = "items")]
+static ITEMS_ARCHIVE: &Aligned<[u8]> = &Aligned(*include_bytes!("items.rkyv"));
+
+#[cfg(feature = "items")]
+fn embedded_items() -> &'static rkyv::Archived<Vec<Item>> {
+    // SAFETY: the archive is written by wiki-data-cli and validated in the tests below.
+    unsafe { rkyv::access_unchecked(&ITEMS_ARCHIVE.0) }
+}
+
 #[cfg(feature = "items")]
 lazy_static::lazy_static! {
-    pub static ref ITEMS: Vec<Item> = rmp_serde::from_slice(include_bytes!("items.bin")).unwrap();
+    pub static ref ITEMS: item::ItemIndex = item::ItemIndex::new(embedded_items());
+}
+
+#[cfg(feature = "recipes")]
+static RECIPES_ARCHIVE: &Aligned<[u8]> = &Aligned(*include_bytes!("recipes.rkyv"));
+
+#[cfg(feature = "recipes")]
+fn embedded_recipes() -> &'static rkyv::Archived<Vec<Recipe>> {
+    // SAFETY: the archive is written by wiki-data-cli and validated in the tests below.
+    unsafe { rkyv::access_unchecked(&RECIPES_ARCHIVE.0) }
+}
+
+#[cfg(feature = "recipes")]
+lazy_static::lazy_static! {
+    pub static ref RECIPES: recipe::RecipeGraph = recipe::RecipeGraph::new(embedded_recipes());
+}
+
+#[cfg(feature = "images")]
+static IMAGES_ARCHIVE: &Aligned<[u8]> = &Aligned(*include_bytes!("images.rkyv"));
+
+#[cfg(feature = "images")]
+fn embedded_images() -> &'static rkyv::Archived<Vec<Image>> {
+    // SAFETY: the archive is written by wiki-data-cli and validated in the tests below.
+    unsafe { rkyv::access_unchecked(&IMAGES_ARCHIVE.0) }
 }
 
 #[cfg(feature = "images")]
 lazy_static::lazy_static! {
-    pub static ref IMAGES: Vec<ImageRef<'static>> = rmp_serde::from_slice(include_bytes!("images.bin")).unwrap();
+    pub static ref IMAGES: image::ImageIndex = image::ImageIndex::new(embedded_images());
+}
+
+#[cfg(test)]
+mod tests {
+    use rkyv::rancor;
+
+    use super::*;
+
+    #[cfg(feature = "items")]
+    #[test]
+    fn items_archive_is_valid() {
+        rkyv::access::<rkyv::Archived<Vec<Item>>, rancor::Error>(&ITEMS_ARCHIVE.0).unwrap();
+    }
+
+    #[cfg(feature = "recipes")]
+    #[test]
+    fn recipes_archive_is_valid() {
+        rkyv::access::<rkyv::Archived<Vec<Recipe>>, rancor::Error>(&RECIPES_ARCHIVE.0).unwrap();
+    }
+
+    #[cfg(feature = "recipes")]
+    #[test]
+    #[ignore = "recipes.rkyv is empty; regenerate it with wiki-data-cli"]
+    fn known_recipes() {
+        assert!(!RECIPES.is_empty());
+
+        let pickaxe = RECIPES.producing("Copper Pickaxe").next().unwrap();
+        let mut ingredients = pickaxe
+            .ingredients
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect::<Vec<_>>();
+        ingredients.sort();
+        assert_eq!(ingredients, ["Copper Bar", "Wood"]);
+
+        assert!(
+            RECIPES
+                .using("Copper Bar")
+                .any(|r| r.result == "Copper Pickaxe")
+        );
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn images_archive_is_valid() {
+        rkyv::access::<rkyv::Archived<Vec<Image>>, rancor::Error>(&IMAGES_ARCHIVE.0).unwrap();
+    }
 }
@@ -0,0 +1,111 @@
+use rkyv::{Archive, rancor, util::AlignedVec};
+
+use crate::{Image, Item, Recipe, image::ImageIndex, item::ItemIndex, recipe::RecipeGraph};
+
+/// The on-disk format of a data pack: everything the app needs from one wiki, in a single rkyv
+/// archive. Written by wiki-data-cli.
+#[derive(Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Pack {
+    /// Short identifier of the source this pack was built from, like "calamity". Used to tell
+    /// apart items with the same name in different packs.
+    pub namespace: String,
+    /// Display name, like "Calamity Mod".
+    pub name: String,
+    /// Base URL of the wiki's article pages, like "https://terraria.wiki.gg/wiki/".
+    pub wiki_url: String,
+    pub items: Vec<Item>,
+    pub recipes: Vec<Recipe>,
+    pub images: Vec<Image>,
+}
+
+/// A loaded data pack, indexed for lookups.
+pub struct DataPack {
+    pub namespace: String,
+    pub name: String,
+    pub wiki_url: String,
+    pub items: ItemIndex,
+    pub recipes: RecipeGraph,
+    pub images: ImageIndex,
+}
+
+impl DataPack {
+    /// The data compiled into this crate.
+    #[cfg(all(feature = "items", feature = "recipes", feature = "images"))]
+    pub fn embedded() -> Self {
+        Self {
+            namespace: "terraria".to_owned(),
+            name: "Terraria".to_owned(),
+            wiki_url: "https://terraria.wiki.gg/wiki/".to_owned(),
+            items: ItemIndex::new(crate::embedded_items()),
+            recipes: RecipeGraph::new(crate::embedded_recipes()),
+            images: ImageIndex::new(crate::embedded_images()),
+        }
+    }
+
+    /// Loads a pack file. The bytes are copied into an aligned buffer that is kept alive for the
+    /// rest of the program, so lookups can hand out `'static` references into it.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, rancor::Error> {
+        let mut aligned = AlignedVec::<16>::with_capacity(bytes.len());
+        aligned.extend_from_slice(bytes);
+
+        rkyv::access::<ArchivedPack, rancor::Error>(&aligned)?;
+
+        let bytes: &'static [u8] = aligned.leak();
+        // SAFETY: the same bytes were validated above.
+        let pack = unsafe { rkyv::access_unchecked::<ArchivedPack>(bytes) };
+
+        Ok(Self {
+            namespace: pack.namespace.to_string(),
+            name: pack.name.to_string(),
+            wiki_url: pack.wiki_url.to_string(),
+            items: ItemIndex::new(&pack.items),
+            recipes: RecipeGraph::new(&pack.recipes),
+            images: ImageIndex::new(&pack.images),
+        })
+    }
+
+    /// Link to the wiki article about `title`.
+    pub fn wiki_page(&self, title: &str) -> String {
+        format!("{}{}", self.wiki_url, title.replace(' ', "_"))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Result<Self, rancor::Error> {
+        use rancor::ResultExt as _;
+
+        let bytes = std::fs::read(path).into_error()?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let pack = Pack {
+            namespace: "test".to_owned(),
+            name: "Test".to_owned(),
+            wiki_url: "https://example.com/wiki/".to_owned(),
+            items: Vec::new(),
+            recipes: Vec::new(),
+            images: vec![Image {
+                name: "Test.png".to_owned(),
+                data: vec![1, 2, 3],
+                width: 16,
+                height: 16,
+            }],
+        };
+
+        let bytes = rkyv::to_bytes::<rancor::Error>(&pack).unwrap();
+        let pack = DataPack::from_bytes(&bytes).unwrap();
+
+        assert_eq!(pack.namespace, "test");
+        assert_eq!(pack.wiki_page("Test Item"), "https://example.com/wiki/Test_Item");
+        assert_eq!(pack.images.by_name("Test.png").unwrap().data.as_slice(), &[1, 2, 3]);
+        assert!(pack.items.is_empty());
+
+        assert!(DataPack::from_bytes(&[1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use std::{collections::HashMap, ops::Deref};
+
+use rkyv::vec::ArchivedVec;
+
+use super::ArchivedRecipe;
+
+/// All recipes, indexed by the items they produce and the items they consume.
+pub struct RecipeGraph {
+    recipes: &'static ArchivedVec<ArchivedRecipe>,
+    by_result: HashMap<&'static str, Vec<usize>>,
+    by_ingredient: HashMap<&'static str, Vec<usize>>,
+}
+
+impl RecipeGraph {
+    pub fn new(recipes: &'static ArchivedVec<ArchivedRecipe>) -> Self {
+        let mut by_result = HashMap::<_, Vec<_>>::new();
+        let mut by_ingredient = HashMap::<_, Vec<_>>::new();
+
+        for (i, recipe) in recipes.iter().enumerate() {
+            by_result.entry(recipe.result.as_str()).or_default().push(i);
+
+            for ingredient in recipe.ingredients.iter() {
+                let uses = by_ingredient.entry(ingredient.name.as_str()).or_default();
+                if uses.last() != Some(&i) {
+                    uses.push(i);
+                }
+            }
+        }
+
+        Self { recipes, by_result, by_ingredient }
+    }
+
+    /// Archives `recipes` and indexes them, for tests. The archive is leaked, like a loaded
+    /// pack's.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn from_recipes(recipes: Vec<super::Recipe>) -> Result<Self, rkyv::rancor::Error> {
+        use rkyv::rancor;
+
+        let bytes = rkyv::to_bytes::<rancor::Error>(&recipes)?.leak();
+        Ok(Self::new(rkyv::access::<_, rancor::Error>(bytes)?))
+    }
+
+    /// Recipes whose result is the item with the given name.
+    pub fn producing(&self, name: &str) -> impl Iterator<Item = &'static ArchivedRecipe> {
+        self.lookup(&self.by_result, name)
+    }
+
+    /// Recipes that take the item with the given name as an ingredient.
+    pub fn using(&self, name: &str) -> impl Iterator<Item = &'static ArchivedRecipe> {
+        self.lookup(&self.by_ingredient, name)
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        index: &'a HashMap<&'static str, Vec<usize>>,
+        name: &str,
+    ) -> impl Iterator<Item = &'static ArchivedRecipe> + 'a {
+        let recipes = self.recipes;
+
+        index
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(move |&i| &recipes[i])
+    }
+}
+
+impl Deref for RecipeGraph {
+    type Target = [ArchivedRecipe];
+
+    fn deref(&self) -> &Self::Target {
+        self.recipes
+    }
+}
@@ -0,0 +1,10 @@
+mod graph;
+mod raw;
+mod recipe;
+
+pub use graph::RecipeGraph;
+pub use raw::RawRecipe;
+pub use recipe::{
+    ArchivedCondition, ArchivedIngredient, ArchivedRecipe, ArchivedStation, Condition, Ingredient,
+    Recipe, Station,
+};
@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RawRecipe {
+    result: String,              // String
+    resultid: Option<String>,    // Integer
+    resultimage: Option<String>, // Wikitext
+    resulttext: Option<String>,  // Wikitext
+    amount: Option<String>,      // Integer
+    version: Option<String>,     // String
+    station: Option<String>,     // String
+    ingredients: Option<String>, // List of String, delimiter: ^
+    args: Option<String>,        // String
+}
+
+impl RawRecipe {
+    pub fn fields() -> Vec<String> {
+        serde_json::to_value(RawRecipe::default())
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.to_string())
+            .collect()
+    }
+
+    pub fn result(&self) -> String {
+        Html::parse_fragment(&self.result)
+            .root_element()
+            .text()
+            .collect()
+    }
+
+    pub fn resultid(&self) -> Option<i32> {
+        Self::integer(&self.resultid)
+    }
+
+    pub fn amount(&self) -> Option<u32> {
+        Self::integer(&self.amount)
+    }
+
+    pub fn version(&self) -> Option<String> {
+        self.version.clone().filter(|s| !s.is_empty())
+    }
+
+    pub fn station(&self) -> Option<String> {
+        self.station.clone().filter(|s| !s.is_empty())
+    }
+
+    pub fn ingredients(&self) -> Vec<String> {
+        self.ingredients
+            .as_deref()
+            .map(|s| s.split('^').map(|s| s.to_owned()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Ingredients with their amounts, encoded as `name¦amount` pairs separated by `^`.
+    pub fn args(&self) -> Option<String> {
+        self.args.clone().filter(|s| !s.is_empty())
+    }
+
+    fn integer<N: FromStr>(field: &Option<String>) -> Option<N> {
+        field.as_deref().and_then(|s| s.parse().ok())
+    }
+}
@@ -0,0 +1,258 @@
+use core::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use rkyv::{Archive, rancor::Panic};
+use serde::{Deserialize, Serialize};
+
+use super::raw::RawRecipe;
+
+#[derive(Debug, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Recipe {
+    pub result: String,
+    pub result_id: Option<i32>,
+    pub amount: u32,
+    pub ingredients: Vec<Ingredient>,
+    pub stations: Vec<Station>,
+    pub conditions: Vec<Condition>,
+    pub version: Option<String>,
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct Ingredient {
+    pub name: String,
+    pub amount: u32,
+}
+
+impl Ingredient {
+    /// Whether this is a recipe group such as "Any Wood" rather than a single item.
+    pub fn is_group(&self) -> bool {
+        self.name.starts_with("Any ")
+    }
+}
+
+impl ArchivedIngredient {
+    /// Whether this is a recipe group such as "Any Wood" rather than a single item.
+    pub fn is_group(&self) -> bool {
+        self.name.starts_with("Any ")
+    }
+}
+
+/// A crafting station requirement, satisfied by any one of its alternatives.
+#[derive(
+    Debug, Clone, PartialEq, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(PartialEq))]
+pub struct Station {
+    pub alternatives: Vec<String>,
+}
+
+impl Display for Station {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.alternatives.join(" or "))
+    }
+}
+
+impl Display for ArchivedStation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, alternative) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                write!(f, " or ")?;
+            }
+            write!(f, "{alternative}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum Condition {
+    NearWater,
+    NearHoney,
+    NearLava,
+    SnowBiome,
+    Graveyard,
+}
+
+impl FromStr for Condition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "water" | "sink" => Ok(Self::NearWater),
+            "honey" => Ok(Self::NearHoney),
+            "lava" => Ok(Self::NearLava),
+            "snow biome" => Ok(Self::SnowBiome),
+            "graveyard" | "ecto mist" => Ok(Self::Graveyard),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NearWater => write!(f, "Near water"),
+            Self::NearHoney => write!(f, "Near honey"),
+            Self::NearLava => write!(f, "Near lava"),
+            Self::SnowBiome => write!(f, "In snow biome"),
+            Self::Graveyard => write!(f, "In graveyard"),
+        }
+    }
+}
+
+impl Display for ArchivedCondition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        rkyv::deserialize::<Condition, Panic>(self).unwrap().fmt(f)
+    }
+}
+
+impl Recipe {
+    pub fn from_raw(recipe: &RawRecipe) -> Option<Self> {
+        let result = recipe.result();
+        if result.is_empty() {
+            return None;
+        }
+
+        let (stations, conditions) = recipe
+            .station()
+            .map(|s| parse_station(&s))
+            .unwrap_or_default();
+
+        Some(Self {
+            result,
+            result_id: recipe.resultid(),
+            amount: recipe.amount().unwrap_or(1),
+            ingredients: recipe
+                .args()
+                .map(|args| parse_ingredients(&args))
+                .unwrap_or_else(|| {
+                    recipe
+                        .ingredients()
+                        .into_iter()
+                        .map(|name| Ingredient { name, amount: 1 })
+                        .collect()
+                }),
+            stations,
+            conditions,
+            version: recipe.version(),
+        })
+    }
+
+    pub fn uses(&self, name: &str) -> bool {
+        self.ingredients.iter().any(|i| i.name == name)
+    }
+}
+
+impl ArchivedRecipe {
+    pub fn uses(&self, name: &str) -> bool {
+        self.ingredients.iter().any(|i| i.name == name)
+    }
+}
+
+fn parse_ingredients(args: &str) -> Vec<Ingredient> {
+    args.split('^')
+        .filter_map(|arg| {
+            let (name, amount) = arg.split_once('¦').unwrap_or((arg, "1"));
+            let name = name.trim();
+
+            (!name.is_empty()).then(|| Ingredient {
+                name: name.to_owned(),
+                amount: amount.trim().parse().unwrap_or(1),
+            })
+        })
+        .collect()
+}
+
+/// Splits a station string like "Placed Bottle and Water" into the stations and environmental
+/// conditions it requires. Alternatives are separated by "or", requirements by "and".
+fn parse_station(s: &str) -> (Vec<Station>, Vec<Condition>) {
+    let mut stations = Vec::new();
+    let mut conditions = Vec::new();
+
+    for requirement in s.split(" and ").map(str::trim) {
+        if requirement.is_empty() || requirement.eq_ignore_ascii_case("by hand") {
+            continue;
+        }
+
+        let alternatives = requirement.split(" or ").map(str::trim).collect::<Vec<_>>();
+
+        let condition = alternatives
+            .iter()
+            .map(|a| a.parse::<Condition>().ok())
+            .reduce(|a, b| if a == b { a } else { None })
+            .flatten();
+
+        match condition {
+            Some(condition) => conditions.push(condition),
+            None => stations.push(Station {
+                alternatives: alternatives.into_iter().map(|a| a.to_owned()).collect(),
+            }),
+        }
+    }
+
+    (stations, conditions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ingredients() {
+        assert_eq!(
+            parse_ingredients("Wood¦10^Iron Bar¦2"),
+            vec![
+                Ingredient { name: "Wood".to_owned(), amount: 10 },
+                Ingredient {
+                    name: "Iron Bar".to_owned(),
+                    amount: 2
+                },
+            ]
+        );
+        assert_eq!(
+            parse_ingredients("Gel"),
+            vec![Ingredient { name: "Gel".to_owned(), amount: 1 }]
+        );
+    }
+
+    #[test]
+    fn station() {
+        assert_eq!(parse_station("By Hand"), (vec![], vec![]));
+
+        let (stations, conditions) = parse_station("Placed Bottle and Water");
+        assert_eq!(
+            stations,
+            vec![Station {
+                alternatives: vec!["Placed Bottle".to_owned()]
+            }]
+        );
+        assert_eq!(conditions, vec![Condition::NearWater]);
+
+        let (stations, conditions) = parse_station("Demon Altar or Crimson Altar");
+        assert_eq!(
+            stations,
+            vec![Station {
+                alternatives: vec!["Demon Altar".to_owned(), "Crimson Altar".to_owned()],
+            }]
+        );
+        assert!(conditions.is_empty());
+
+        let (stations, conditions) = parse_station("Water or Sink");
+        assert!(stations.is_empty());
+        assert_eq!(conditions, vec![Condition::NearWater]);
+    }
+}